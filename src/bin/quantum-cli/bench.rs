@@ -0,0 +1,86 @@
+use std::time::Instant;
+use quantum::globals::*;
+use quantum::world::World;
+use quantum::interaction::ForceLaw;
+use quantum::physics::GravityMode;
+
+
+const BENCH_SIZES: [usize; 3] = [1_000, 10_000, 50_000];
+const BENCH_STEPS: usize = 20;
+/// World area per quant, the world grows with quants number to keep density constant.
+const AREA_PER_QUANT: f32 = 900.0;
+/// Brute-force gravity is quadratic, so it's timed only up to this number of quants.
+const GRAV_COMPARE_MAX: usize = 10_000;
+
+/// Measures custom force computation and full step time for growing numbers of quants.
+/// Run with `cargo run --release --bin quantum-cli -- bench`.
+pub fn run_bench() {
+    println!("[BENCH]: {} steps per size, interaction range: {}", BENCH_STEPS, ForceLaw::default().range);
+    for quants_num in BENCH_SIZES {
+        bench_world(quants_num);
+    }
+    println!("[BENCH]: gravity, theta: {}", Settings::default().grav_theta);
+    for quants_num in BENCH_SIZES {
+        bench_gravity(quants_num);
+    }
+}
+
+fn make_world(quants_num: usize) -> World {
+    let side = (quants_num as f32 * AREA_PER_QUANT).sqrt();
+    let settings = Settings {
+        seed: 1,
+        world_w: side as i32,
+        world_h: side as i32,
+        quant_min_num: 0,
+        bond_activation: 0.0,
+        ..Default::default()
+    };
+    let mut world = World::new(settings);
+    let elements_num = world.elements.count();
+    for i in 0..quants_num {
+        world.add_element(i % elements_num);
+    }
+    return world;
+}
+
+fn bench_world(quants_num: usize) {
+    let mut world = make_world(quants_num);
+    world.interactions.randomize(&world.rng, 5000.0);
+    world.step();
+
+    let t = Instant::now();
+    for _ in 0..BENCH_STEPS {
+        let forces = world.compute_interactions();
+        assert_eq!(forces.len(), quants_num);
+    }
+    let forces_time = t.elapsed().as_secs_f64() * 1000.0 / BENCH_STEPS as f64;
+
+    let t = Instant::now();
+    for _ in 0..BENCH_STEPS {
+        world.step();
+    }
+    let step_time = t.elapsed().as_secs_f64() * 1000.0 / BENCH_STEPS as f64;
+    println!("[BENCH]: {:>6} quants | forces: {:>9.3} ms | step: {:>9.3} ms", quants_num, forces_time, step_time);
+}
+
+/// Times Barnes–Hut gravity against the exact brute-force sum, accuracy is checked by `tests/gravity.rs`.
+fn bench_gravity(quants_num: usize) {
+    let mut world = make_world(quants_num);
+    world.settings.gravity_mode = GravityMode::BarnesHut;
+    world.step();
+    let theta = world.settings.grav_theta;
+
+    let t = Instant::now();
+    let approx = world.physics.compute_grav_barnes_hut(theta, None);
+    let bh_time = t.elapsed().as_secs_f64() * 1000.0;
+    if quants_num > GRAV_COMPARE_MAX {
+        println!("[BENCH]: {:>6} quants | barnes-hut: {:>9.3} ms", quants_num, bh_time);
+        return;
+    }
+
+    let t = Instant::now();
+    let exact = world.physics.compute_grav(None);
+    let bf_time = t.elapsed().as_secs_f64() * 1000.0;
+    assert_eq!(exact.len(), approx.len());
+    println!("[BENCH]: {:>6} quants | barnes-hut: {:>9.3} ms | brute force: {:>9.3} ms", quants_num, bh_time, bf_time);
+}
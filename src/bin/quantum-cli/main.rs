@@ -0,0 +1,248 @@
+#![allow(clippy::needless_return)]
+
+//! Headless runner: builds a world from a scenario or snapshot, steps it and saves the results.
+//!
+//! ```text
+//! quantum-cli [--scenario FILE | --snapshot FILE] [--steps N] [--seed N] [--set NAME=VALUE]...
+//!             [--out FILE] [--metrics FILE] [--every N] [--census FILE] [--verbose]
+//! quantum-cli sweep FILE [--out FILE] [--threads N]
+//! quantum-cli bench
+//! ```
+
+mod bench;
+
+use std::process::exit;
+use std::time::Instant;
+use quantum::globals::*;
+use quantum::runner;
+use quantum::scenario::Scenario;
+use quantum::snapshot::Snapshot;
+use quantum::sweep::Sweep;
+use quantum::world::World;
+
+
+const USAGE: &str = "usage: quantum-cli [--scenario FILE | --snapshot FILE] [--steps N] [--seed N] [--set NAME=VALUE]...
+                   [--out FILE] [--metrics FILE] [--every N] [--census FILE] [--verbose]
+       quantum-cli sweep FILE [--out FILE] [--threads N]
+       quantum-cli bench";
+/// Seconds between progress reports.
+const PROGRESS_INTERVAL: f64 = 1.0;
+
+struct Args {
+    scenario: Option<String>,
+    snapshot: Option<String>,
+    steps: usize,
+    seed: Option<u64>,
+    overrides: Vec<(String, String)>,
+    out: String,
+    metrics: String,
+    every: usize,
+    census: Option<String>,
+    verbose: bool,
+}
+
+impl Args {
+
+    fn parse() -> Result<Self, String> {
+        let mut args = Self {
+            scenario: None,
+            snapshot: None,
+            steps: 1000,
+            seed: None,
+            overrides: vec![],
+            out: String::from("final.json"),
+            metrics: String::from("metrics.csv"),
+            every: 100,
+            census: None,
+            verbose: false,
+        };
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| iter.next().ok_or(format!("{} needs a value", name));
+            match arg.as_str() {
+                "--scenario" => args.scenario = Some(value(&arg)?),
+                "--snapshot" => args.snapshot = Some(value(&arg)?),
+                "--steps" => args.steps = parse_number(&arg, &value(&arg)?)?,
+                "--seed" => args.seed = Some(parse_number(&arg, &value(&arg)?)?),
+                "--set" => {
+                    let pair = value(&arg)?;
+                    match pair.split_once('=') {
+                        Some((name, v)) => args.overrides.push((name.to_string(), v.to_string())),
+                        None => return Err(format!("--set expects NAME=VALUE, got '{}'", pair)),
+                    }
+                },
+                "--out" => args.out = value(&arg)?,
+                "--metrics" => args.metrics = value(&arg)?,
+                "--every" => args.every = parse_number(&arg, &value(&arg)?)?,
+                "--census" => args.census = Some(value(&arg)?),
+                "--verbose" => args.verbose = true,
+                "--help" | "-h" => {
+                    println!("{}", USAGE);
+                    exit(0);
+                },
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+        if args.scenario.is_some() && args.snapshot.is_some() {
+            return Err(String::from("use either --scenario or --snapshot"));
+        }
+        return Ok(args);
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    return value.parse().map_err(|_| format!("{} expects a number, got '{}'", name, value));
+}
+
+/// Settings with seed and `--set` overrides applied.
+fn override_settings(mut settings: Settings, args: &Args) -> Result<Settings, String> {
+    for (name, value) in args.overrides.iter() {
+        settings.set_field(name, value)?;
+    }
+    if let Some(seed) = args.seed {
+        settings.seed = seed;
+    }
+    return Ok(settings);
+}
+
+fn build_world(args: &Args) -> Result<World, String> {
+    if let Some(path) = &args.snapshot {
+        let snapshot = Snapshot::load(path).map_err(|e| format!("can't load {}: {}", path, e))?;
+        let mut world = snapshot.to_world().map_err(|e| format!("can't load {}: {}", path, e))?;
+        let settings = override_settings(world.settings, args)?;
+        world.apply_settings(settings);
+        world.rng.srand(settings.seed);
+        return Ok(world);
+    }
+    let scenario = match &args.scenario {
+        Some(path) => Scenario::load(path).map_err(|e| format!("can't load {}: {}", path, e))?,
+        None => Scenario::default(),
+    };
+    let settings = override_settings(scenario.settings, args)?;
+    return scenario.build(settings).map_err(|e| e.to_string());
+}
+
+/// Runs a sweep file and writes the summary CSV.
+fn sweep(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let path = args.next().ok_or("sweep needs a file")?;
+    let mut out = String::from("sweep.csv");
+    let mut threads = None;
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--out" => out = value,
+            "--threads" => threads = Some(parse_number(&arg, &value)?),
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+    let mut sweep = Sweep::load(&path).map_err(|e| format!("can't load {}: {}", path, e))?;
+    if threads.is_some() {
+        sweep.threads = threads;
+    }
+    let scenario = match &sweep.scenario {
+        Some(path) => Scenario::load(path).map_err(|e| format!("can't load {}: {}", path, e))?,
+        None => Scenario::default(),
+    };
+    sweep.check(&scenario)?;
+    let runs = sweep.combinations().len();
+    eprintln!("[SWEEP]: {} runs of {} steps on {} threads", runs, sweep.steps, sweep.get_threads().min(runs));
+
+    let start = Instant::now();
+    let mut done = 0;
+    let results = sweep.run(&scenario, |result, total| {
+        done += 1;
+        let params: Vec<String> = result.params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        match &result.stats {
+            Ok(_) => eprintln!("[SWEEP]: {}/{} run {} ({}) done in {:.2}s", done, total, result.index, params.join(" "), result.elapsed),
+            Err(e) => eprintln!("[SWEEP]: {}/{} run {} ({}) failed: {}", done, total, result.index, params.join(" "), e),
+        }
+    });
+    let failed = results.iter().filter(|r| r.stats.is_err()).count();
+    eprintln!("[SWEEP]: {} runs in {:.2}s, {} failed", runs, start.elapsed().as_secs_f64(), failed);
+    quantum::sweep::save_summary(&out, &sweep, &results).map_err(|e| format!("can't save {}: {}", out, e))?;
+    eprintln!("[SWEEP]: summary saved to {}", out);
+    if failed > 0 {
+        return Err(format!("{} of {} runs failed", failed, runs));
+    }
+    return Ok(());
+}
+
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some("sweep") => {
+            if let Err(e) = sweep(std::env::args().skip(2)) {
+                eprintln!("[SWEEP]: {}", e);
+                exit(1);
+            }
+            return;
+        },
+        Some("bench") => {
+            if let Some(arg) = std::env::args().nth(2) {
+                eprintln!("[BENCH]: unknown argument '{}'\n{}", arg, USAGE);
+                exit(2);
+            }
+            bench::run_bench();
+            return;
+        },
+        _ => {},
+    }
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("[CLI]: {}\n{}", e, USAGE);
+            exit(2);
+        },
+    };
+    let mut world = match build_world(&args) {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("[CLI]: {}", e);
+            exit(1);
+        },
+    };
+    world.verbose = args.verbose;
+    eprintln!("[CLI]: seed: {} quants: {} steps: {}", world.settings.seed, world.quants_num(), args.steps);
+
+    let start = Instant::now();
+    let mut last_report = 0.0;
+    let mut last_step = 0;
+    let samples = runner::run(&mut world, args.steps, args.every, |step| {
+        let elapsed = start.elapsed().as_secs_f64();
+        if elapsed - last_report >= PROGRESS_INTERVAL {
+            let rate = (step - last_step) as f64 / (elapsed - last_report);
+            eprintln!("[CLI]: step {}/{} ({:.0}%) | {:.0} steps/s", step, args.steps, 100.0 * step as f64 / args.steps as f64, rate);
+            last_report = elapsed;
+            last_step = step;
+        }
+    });
+    let elapsed = start.elapsed().as_secs_f64();
+    eprintln!("[CLI]: {} steps in {:.2}s | {:.0} steps/s | sim time: {:.2}s", args.steps, elapsed, args.steps as f64 / elapsed.max(f64::EPSILON), world.sim_time);
+
+    let mut failed = false;
+    match Snapshot::from_world(&world).save(&args.out) {
+        Ok(_) => eprintln!("[CLI]: snapshot saved to {}", args.out),
+        Err(e) => {
+            eprintln!("[CLI]: can't save {}: {}", args.out, e);
+            failed = true;
+        },
+    }
+    match runner::save_metrics(&args.metrics, &samples) {
+        Ok(_) => eprintln!("[CLI]: metrics saved to {}", args.metrics),
+        Err(e) => {
+            eprintln!("[CLI]: can't save {}: {}", args.metrics, e);
+            failed = true;
+        },
+    }
+    if let Some(path) = &args.census {
+        match world.census.export(path) {
+            Ok(_) => eprintln!("[CLI]: census saved to {}", path),
+            Err(e) => {
+                eprintln!("[CLI]: can't save {}: {}", path, e);
+                failed = true;
+            },
+        }
+    }
+    if failed {
+        exit(1);
+    }
+}
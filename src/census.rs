@@ -0,0 +1,280 @@
+#![allow(unused)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use quad_rand::RandGenerator;
+use crate::util::create_name;
+use crate::collector::ElementsBox;
+use crate::tracker::MoleculeTracker;
+
+
+/// Syllables in generated species names.
+const NAME_LENGTH: usize = 3;
+
+fn mix(hash: u64, value: u64) -> u64 {
+    let mut x = hash ^ value.wrapping_mul(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return x ^ (x >> 31);
+}
+
+/// Canonical hash of element-labelled graph, independent of atom and bond order.
+/// `labels` are element ids of atoms, `edges` pairs of atom indices.
+/// Atoms are first colored with Weisfeiler-Lehman refinement: every atom label is repeatedly combined
+/// with sorted labels of its neighbors until the partition stops changing. Atoms left in the same class
+/// (e.g. all atoms of triangular prism or K3,3, which WL alone can't tell apart) are individualized one
+/// by one and refined again; the smallest relabeled graph over all branches is hashed, so only
+/// isomorphic molecules share a fingerprint.
+pub fn fingerprint(labels: &[usize], edges: &[(usize, usize)]) -> u64 {
+    let n = labels.len();
+    let mut neighbors: Vec<Vec<usize>> = vec![vec![]; n];
+    for (a, b) in edges.iter() {
+        neighbors[*a].push(*b);
+        neighbors[*b].push(*a);
+    }
+    for around in neighbors.iter_mut() {
+        around.sort_unstable();
+    }
+    let colors: Vec<u64> = (0..n).map(|i| mix(mix(0, labels[i] as u64 + 1), neighbors[i].len() as u64)).collect();
+    let mut search = Search { labels, neighbors, best: None, automorphisms: vec![] };
+    search.branch(colors, &mut vec![]);
+    let certificate = search.best.map(|(certificate, _)| certificate).unwrap_or_default();
+    return certificate.iter().fold(mix(n as u64, edges.len() as u64), |h, c| mix(h, *c));
+}
+
+/// Individualization-refinement search for the smallest certificate of a graph.
+struct Search<'a> {
+    labels: &'a [usize],
+    neighbors: Vec<Vec<usize>>,
+    /// Smallest certificate found so far and the atom order that gave it.
+    best: Option<(Vec<u64>, Vec<usize>)>,
+    /// Atom permutations found by reaching the same certificate twice.
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+
+    /// Refines `colors`, then branches on every atom of the smallest ambiguous class until all atoms
+    /// have distinct colors. `path` holds the atoms individualized so far.
+    fn branch(&mut self, colors: Vec<u64>, path: &mut Vec<usize>) {
+        let colors = refine(&self.neighbors, colors);
+        let mut order: Vec<usize> = (0..colors.len()).collect();
+        order.sort_unstable_by_key(|i| colors[*i]);
+        // the choice of class must not depend on atom order: smallest size, then smallest color
+        let mut cell: Option<&[usize]> = None;
+        for class in order.chunk_by(|a, b| colors[*a] == colors[*b]) {
+            if class.len() > 1 && cell.is_none_or(|c| (class.len(), colors[class[0]]) < (c.len(), colors[c[0]])) {
+                cell = Some(class);
+            }
+        }
+        let cell = match cell {
+            Some(cell) => cell.to_vec(),
+            None => {
+                self.leaf(order);
+                return;
+            },
+        };
+        let mut tried: Vec<usize> = vec![];
+        for v in cell.iter() {
+            // swapping twins or mapping by a known automorphism that keeps the path in place
+            // leads to the same certificates as an atom already tried
+            if tried.iter().any(|u| are_twins(&self.neighbors, *u, *v)) || self.in_orbit(path, &tried, *v) {
+                continue;
+            }
+            tried.push(*v);
+            let mut individualized = colors.clone();
+            individualized[*v] = mix(individualized[*v], u64::MAX);
+            path.push(*v);
+            self.branch(individualized, path);
+            path.pop();
+        }
+    }
+
+    fn leaf(&mut self, order: Vec<usize>) {
+        let certificate = certificate(self.labels, &self.neighbors, &order);
+        match &self.best {
+            Some((best, best_order)) if certificate == *best => {
+                let mut automorphism = vec![0; order.len()];
+                for (a, b) in best_order.iter().zip(order.iter()) {
+                    automorphism[*a] = *b;
+                }
+                self.automorphisms.push(automorphism);
+            },
+            Some((best, _)) if certificate > *best => {},
+            _ => self.best = Some((certificate, order)),
+        }
+    }
+
+    /// Whether `v` is mapped onto one of `tried` by automorphisms fixing every atom of `path`.
+    fn in_orbit(&self, path: &[usize], tried: &[usize], v: usize) -> bool {
+        if tried.is_empty() {
+            return false;
+        }
+        let mut parent: Vec<usize> = (0..self.neighbors.len()).collect();
+        for automorphism in self.automorphisms.iter().filter(|a| path.iter().all(|p| a[*p] == *p)) {
+            for (i, j) in automorphism.iter().enumerate() {
+                let (ri, rj) = (root(&mut parent, i), root(&mut parent, *j));
+                parent[ri] = rj;
+            }
+        }
+        let rv = root(&mut parent, v);
+        return tried.iter().any(|u| root(&mut parent, *u) == rv);
+    }
+}
+
+/// Union-find root with path halving.
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    return i;
+}
+
+/// WL refinement until the number of color classes stops growing.
+fn refine(neighbors: &[Vec<usize>], mut colors: Vec<u64>) -> Vec<u64> {
+    let n = colors.len();
+    let mut classes = count_classes(&colors);
+    for _ in 0..n {
+        let mut next: Vec<u64> = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut around: Vec<u64> = neighbors[i].iter().map(|j| colors[*j]).collect();
+            around.sort_unstable();
+            next.push(around.iter().fold(mix(0, colors[i]), |h, c| mix(h, *c)));
+        }
+        colors = next;
+        let next_classes = count_classes(&colors);
+        if next_classes == classes {
+            break;
+        }
+        classes = next_classes;
+    }
+    return colors;
+}
+
+/// Atoms with the same neighbors apart from each other, like hydrogens of one carbon.
+fn are_twins(neighbors: &[Vec<usize>], a: usize, b: usize) -> bool {
+    let a_around = neighbors[a].iter().filter(|i| **i != b);
+    let b_around = neighbors[b].iter().filter(|i| **i != a);
+    return a_around.eq(b_around);
+}
+
+/// Labels and bonds of the graph with atoms renumbered to their position in `order`.
+fn certificate(labels: &[usize], neighbors: &[Vec<usize>], order: &[usize]) -> Vec<u64> {
+    let n = order.len();
+    let mut position = vec![0; n];
+    for (p, i) in order.iter().enumerate() {
+        position[*i] = p;
+    }
+    let mut certificate: Vec<u64> = order.iter().map(|i| labels[*i] as u64).collect();
+    let mut bonds: Vec<u64> = vec![];
+    for (a, around) in neighbors.iter().enumerate() {
+        for b in around.iter().filter(|b| position[**b] > position[a]) {
+            bonds.push((position[a] * n + position[*b]) as u64);
+        }
+    }
+    bonds.sort_unstable();
+    certificate.extend(bonds);
+    return certificate;
+}
+
+fn count_classes(colors: &[u64]) -> usize {
+    let mut sorted = colors.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    return sorted.len();
+}
+
+/// Readable name generated from fingerprint, the same for every run.
+pub fn species_name(fingerprint: u64) -> String {
+    let rng = RandGenerator::new();
+    rng.srand(fingerprint);
+    return create_name(&rng, NAME_LENGTH);
+}
+
+#[derive(Clone)]
+pub struct Species {
+    pub fingerprint: u64,
+    pub name: String,
+    pub formula: String,
+    pub size: usize,
+    /// Molecules of this species present after the last update.
+    pub count: usize,
+    pub peak: usize,
+    pub first_seen: f64,
+    pub last_seen: f64,
+}
+
+/// Table of all molecule species seen since the world started, keyed by fingerprint.
+#[derive(Default)]
+pub struct Census {
+    species: Vec<Species>,
+    index: HashMap<u64, usize>,
+}
+
+impl Census {
+
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn update(&mut self, tracker: &MoleculeTracker, elements: &ElementsBox, sim_time: f64) {
+        for species in self.species.iter_mut() {
+            species.count = 0;
+        }
+        for molecule in tracker.get_iter() {
+            let i = match self.index.get(&molecule.fingerprint) {
+                Some(i) => *i,
+                None => {
+                    self.species.push(Species {
+                        fingerprint: molecule.fingerprint,
+                        name: species_name(molecule.fingerprint),
+                        formula: molecule.formula(elements),
+                        size: molecule.size(),
+                        count: 0,
+                        peak: 0,
+                        first_seen: sim_time,
+                        last_seen: sim_time,
+                    });
+                    self.index.insert(molecule.fingerprint, self.species.len() - 1);
+                    self.species.len() - 1
+                },
+            };
+            self.species[i].count += 1;
+        }
+        for species in self.species.iter_mut().filter(|s| s.count > 0) {
+            species.peak = species.peak.max(species.count);
+            species.last_seen = sim_time;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.species.clear();
+        self.index.clear();
+    }
+
+    pub fn get(&self, fingerprint: u64) -> Option<&Species> {
+        let i = self.index.get(&fingerprint)?;
+        return self.species.get(*i);
+    }
+
+    pub fn get_iter(&self) -> std::slice::Iter<'_, Species> {
+        return self.species.iter();
+    }
+
+    pub fn count(&self) -> usize {
+        return self.species.len();
+    }
+
+    /// Writes the table as CSV, one species per row in order of discovery.
+    pub fn export(&self, path: &str) -> io::Result<()> {
+        let mut csv = String::from("fingerprint,name,formula,size,count,peak,first_seen,last_seen\n");
+        for s in self.species.iter() {
+            csv.push_str(&format!("{:016X},{},{},{},{},{},{:.3},{:.3}\n",
+                s.fingerprint, s.name, s.formula, s.size, s.count, s.peak, s.first_seen, s.last_seen));
+        }
+        fs::write(path, csv)?;
+        return Ok(());
+    }
+}
@@ -3,25 +3,32 @@
 
 use std::collections::hash_map::{Iter, IterMut};
 use std::collections::HashMap;
+use std::fs;
+use std::io;
 use crate::util::*;
 use crate::physics::*;
 use crate::quant::*;
+use crate::joint::*;
+use crate::element::*;
+use crate::molecule::MoleculeTemplate;
 use crate::globals::*;
-use macroquad::prelude::*;
-use rapier2d::prelude::RigidBodyHandle;
-
-pub trait PhysicsObject {
-    fn new() -> Self;
-    fn draw(&self, selected: bool, font: &Font);
-    fn update(&mut self, dt: f32, physics: &mut Physics) -> bool;
-    fn update_physics(&mut self, physics: &mut Physics);
-    fn link_physics_handle(&mut self, handle: RigidBodyHandle);
-}
+use glam::Vec2;
+use quad_rand::RandGenerator;
+use rapier2d::prelude::{ImpulseJointHandle, RigidBodyHandle};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_ELEMENTS: &str = include_str!("../assets/elements.json");
 
 pub struct QuantumCollector {
     pub quants: HashMap<RigidBodyHandle, Quant>,
 }
 
+impl Default for QuantumCollector {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl QuantumCollector {
     pub fn new() -> Self {
         Self {
@@ -29,15 +36,15 @@ impl QuantumCollector {
         }
     }
 
-    pub fn add_many_quants(&mut self, quants_num: usize, physics: &mut Physics) {
+    pub fn add_many_quants(&mut self, quants_num: usize, element_id: usize, element: &Element, settings: &Settings, rng: &RandGenerator, physics: &mut Physics) {
         for _ in 0..quants_num {
-            let quant = Quant::new(6.0, 3, WHITE, physics);
+            let quant = Quant::new(element_id, element, settings, rng, physics);
             _ = self.add_quant(quant);
         }
     }
 
     pub fn add_quant(&mut self, quant: Quant) -> RigidBodyHandle {
-        let h = quant.physics_handle.clone();
+        let h = quant.physics_handle;
         self.quants.insert(quant.physics_handle, quant);
         return h;
     }
@@ -46,15 +53,20 @@ impl QuantumCollector {
         return self.quants.get(&id);
     }
 
-    pub fn remove(&mut self, id: RigidBodyHandle) {
-        self.quants.remove(&id);
+    pub fn get_mut(&mut self, id: RigidBodyHandle) -> Option<&mut Quant> {
+        return self.quants.get_mut(&id);
     }
 
-    pub fn get_iter(&self) -> Iter<RigidBodyHandle, Quant> {
+    /// Only drops the entry, use `World::despawn_quant` to remove quant with its body and bonds.
+    pub fn remove(&mut self, id: RigidBodyHandle) -> Option<Quant> {
+        return self.quants.remove(&id);
+    }
+
+    pub fn get_iter(&self) -> Iter<'_, RigidBodyHandle, Quant> {
         return self.quants.iter();
     }
 
-    pub fn get_iter_mut(&mut self) -> IterMut<RigidBodyHandle, Quant> {
+    pub fn get_iter_mut(&mut self) -> IterMut<'_, RigidBodyHandle, Quant> {
         return self.quants.iter_mut();
     }
 
@@ -64,6 +76,162 @@ impl QuantumCollector {
 
 }
 
+pub struct JointCollector {
+    pub joints: HashMap<ImpulseJointHandle, MyJoint>,
+}
+
+impl Default for JointCollector {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl JointCollector {
+    pub fn new() -> Self {
+        Self {
+            joints: HashMap::new(),
+        }
+    }
+
+    pub fn add_joint(&mut self, joint: MyJoint) -> ImpulseJointHandle {
+        let h = joint.physics_handle;
+        self.joints.insert(joint.physics_handle, joint);
+        return h;
+    }
+
+    pub fn get(&self, id: ImpulseJointHandle) -> Option<&MyJoint> {
+        return self.joints.get(&id);
+    }
+
+    pub fn get_mut(&mut self, id: ImpulseJointHandle) -> Option<&mut MyJoint> {
+        return self.joints.get_mut(&id);
+    }
+
+    /// Only drops the entry, use `World::remove_bond` to remove the rapier joint too.
+    pub fn remove(&mut self, id: ImpulseJointHandle) -> Option<MyJoint> {
+        return self.joints.remove(&id);
+    }
+
+    pub fn get_iter(&self) -> Iter<'_, ImpulseJointHandle, MyJoint> {
+        return self.joints.iter();
+    }
+
+    pub fn get_iter_mut(&mut self) -> IterMut<'_, ImpulseJointHandle, MyJoint> {
+        return self.joints.iter_mut();
+    }
+
+    pub fn count(&self) -> usize {
+        return self.joints.len();
+    }
+
+}
+
+/// Element table, loaded from JSON file. Quants reference elements by index.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ElementsBox {
+    pub elements: Vec<Element>,
+}
+
+impl Default for ElementsBox {
+    fn default() -> Self {
+        return serde_json::from_str(DEFAULT_ELEMENTS).expect("built-in element table is invalid");
+    }
+}
+
+impl ElementsBox {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let elements: ElementsBox = serde_json::from_str(&json)?;
+        if elements.count() == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "element table is empty"));
+        }
+        return Ok(elements);
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        return Ok(());
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Element> {
+        return self.elements.get(id);
+    }
+
+    pub fn find(&self, symbol: &str) -> Option<usize> {
+        return self.elements.iter().position(|e| e.symbol == symbol);
+    }
+
+    pub fn get_iter(&self) -> std::slice::Iter<'_, Element> {
+        return self.elements.iter();
+    }
+
+    pub fn count(&self) -> usize {
+        return self.elements.len();
+    }
+
+}
+
+
+/// Molecule templates available for spawning, usually loaded from `MOLECULES_PATH` directory.
+#[derive(Clone, Default)]
+pub struct MoleculesBox {
+    templates: Vec<MoleculeTemplate>,
+}
+
+impl MoleculesBox {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Loads every `.json` template in the directory, in file name order.
+    /// Templates which can't be parsed or don't pass validation are skipped with a message.
+    pub fn load_dir(path: &str, elements: &ElementsBox) -> io::Result<Self> {
+        let mut paths: Vec<std::path::PathBuf> = fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        let mut molecules = Self::new();
+        for p in paths.iter() {
+            let name = p.display();
+            match MoleculeTemplate::load(&p.to_string_lossy()) {
+                Ok(template) => match template.validate(elements) {
+                    Ok(_) => molecules.add(template),
+                    Err(e) => println!("[MOLECULES]: skipped {}: {}", name, e),
+                },
+                Err(e) => println!("[MOLECULES]: can't load {}: {}", name, e),
+            }
+        }
+        return Ok(molecules);
+    }
+
+    /// Adds template, replacing one with the same name.
+    pub fn add(&mut self, template: MoleculeTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(t) => *t = template,
+            None => self.templates.push(template),
+        }
+    }
 
-}
\ No newline at end of file
+    pub fn get(&self, id: usize) -> Option<&MoleculeTemplate> {
+        return self.templates.get(id);
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        return self.templates.iter().position(|t| t.name == name);
+    }
+
+    pub fn get_iter(&self) -> std::slice::Iter<'_, MoleculeTemplate> {
+        return self.templates.iter();
+    }
+
+    pub fn count(&self) -> usize {
+        return self.templates.len();
+    }
+
+}
@@ -0,0 +1,46 @@
+#![allow(unused)]
+
+
+/// RGBA color with components in 0.0..1.0 range, layout and palette match macroquad's `Color`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn to_array(&self) -> [f32; 4] {
+        return [self.r, self.g, self.b, self.a];
+    }
+}
+
+pub const LIGHTGRAY: Color = Color::new(0.78, 0.78, 0.78, 1.00);
+pub const GRAY: Color = Color::new(0.51, 0.51, 0.51, 1.00);
+pub const DARKGRAY: Color = Color::new(0.31, 0.31, 0.31, 1.00);
+pub const YELLOW: Color = Color::new(0.99, 0.98, 0.00, 1.00);
+pub const GOLD: Color = Color::new(1.00, 0.80, 0.00, 1.00);
+pub const ORANGE: Color = Color::new(1.00, 0.63, 0.00, 1.00);
+pub const PINK: Color = Color::new(1.00, 0.43, 0.76, 1.00);
+pub const RED: Color = Color::new(0.90, 0.16, 0.22, 1.00);
+pub const MAROON: Color = Color::new(0.75, 0.13, 0.22, 1.00);
+pub const GREEN: Color = Color::new(0.00, 0.89, 0.19, 1.00);
+pub const LIME: Color = Color::new(0.00, 0.62, 0.18, 1.00);
+pub const DARKGREEN: Color = Color::new(0.00, 0.46, 0.17, 1.00);
+pub const SKYBLUE: Color = Color::new(0.40, 0.75, 1.00, 1.00);
+pub const BLUE: Color = Color::new(0.00, 0.47, 0.95, 1.00);
+pub const DARKBLUE: Color = Color::new(0.00, 0.32, 0.67, 1.00);
+pub const PURPLE: Color = Color::new(0.78, 0.48, 1.00, 1.00);
+pub const VIOLET: Color = Color::new(0.53, 0.24, 0.75, 1.00);
+pub const DARKPURPLE: Color = Color::new(0.44, 0.12, 0.49, 1.00);
+pub const BEIGE: Color = Color::new(0.83, 0.69, 0.51, 1.00);
+pub const BROWN: Color = Color::new(0.50, 0.42, 0.31, 1.00);
+pub const DARKBROWN: Color = Color::new(0.30, 0.25, 0.18, 1.00);
+pub const WHITE: Color = Color::new(1.00, 1.00, 1.00, 1.00);
+pub const BLACK: Color = Color::new(0.00, 0.00, 0.00, 1.00);
+pub const MAGENTA: Color = Color::new(1.00, 0.00, 1.00, 1.00);
@@ -0,0 +1,20 @@
+use macroquad::prelude::*;
+use quantum::color;
+use quantum::joint::MyJoint;
+use quantum::quant::Quant;
+
+
+pub fn to_color(c: color::Color) -> Color {
+    return Color::new(c.r, c.g, c.b, c.a);
+}
+
+pub fn draw_quant(quant: &Quant, alpha: f32, period: Option<Vec2>) {
+    let pos = quant.get_draw_position(alpha, period);
+    draw_circle(pos.x, pos.y, quant.size, to_color(quant.color));
+}
+
+pub fn draw_joint(joint: &MyJoint, alpha: f32, period: Option<Vec2>) {
+    for (from, to) in joint.get_draw_segments(alpha, period) {
+        draw_line(from.x, from.y, to.x, to.y, 3.0, to_color(joint.color));
+    }
+}
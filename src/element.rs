@@ -0,0 +1,35 @@
+use crate::physics::PhysicsProperities;
+use crate::color::Color;
+use serde::{Deserialize, Serialize};
+
+
+/// Quant species: appearance, physical properties and number of bonds it can form.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Element {
+    pub name: String,
+    pub symbol: String,
+    pub radius: f32,
+    pub density: f32,
+    /// RGBA components in 0.0..1.0 range.
+    pub color: [f32; 4],
+    pub valence: usize,
+    pub friction: f32,
+    pub restitution: f32,
+}
+
+impl Element {
+
+    pub fn get_color(&self) -> Color {
+        let [r, g, b, a] = self.color;
+        return Color::new(r, g, b, a);
+    }
+
+    pub fn get_physics_props(&self) -> PhysicsProperities {
+        return PhysicsProperities {
+            friction: self.friction,
+            restitution: self.restitution,
+            density: self.density,
+            ..Default::default()
+        };
+    }
+}
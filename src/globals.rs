@@ -1,5 +1,8 @@
 #![allow(unused)]
-use macroquad::experimental::collections::storage;
+use serde::{Deserialize, Serialize};
+use crate::physics::GravityMode;
+use std::fs;
+use std::io;
 
 
 pub const SCREEN_W: f32 = 900.0;
@@ -10,79 +13,133 @@ pub const WORLD_H: f32 = 700.0;
 pub const ZOOM_RATE: f32 = 1.0 / 800.0;
 pub const SCREEN_RATIO: f32 = SCREEN_W / SCREEN_H;
 pub const GRAV: f32 = -2500.0;
+/// Softening length of gravity, keeps force finite for touching bodies.
+pub const GRAV_SOFTENING: f32 = 10.0;
+/// Accepted relative rms error of Barnes–Hut forces at `Settings::grav_theta` default.
+pub const GRAV_MAX_ERROR: f32 = 0.02;
+/// Spring pulling a dragged quant to the mouse, per unit of mass.
+pub const DRAG_STIFFNESS: f32 = 400.0;
+pub const DRAG_DAMPING: f32 = 30.0;
+/// Distance between center and arms of star molecules.
+pub const STAR_ARM_LENGTH: f32 = 14.0;
+pub const WALL_THICKNESS: f32 = 20.0;
+/// Highest simulation speed multiplier.
+pub const MAX_SPEED: usize = 16;
+/// Slow motion divides simulation speed by this.
+pub const SLOW_MOTION_FACTOR: f32 = 4.0;
+/// Limit of physics steps in one frame. When a frame needs more, the rest of the time is dropped
+/// and the simulation runs slower than real time instead of falling further behind every frame.
+pub const MAX_STEPS_PER_FRAME: usize = 32;
+pub const ELEMENTS_PATH: &str = "assets/elements.json";
+pub const MOLECULES_PATH: &str = "assets/molecules";
 
 
-pub fn init_global_settings(settings: Settings) {
-    storage::store(settings);
-}
-
-pub fn get_settings() -> Settings {
-    return *storage::get::<Settings>();
-}
-
-pub fn mod_settings() -> Settings {
-    return *storage::get_mut::<Settings>();
-}
-
-pub fn init_global_signals(signals: Signals) {
-    storage::store(signals);
-}
-
-pub fn get_signals() -> Signals {
-    return *storage::get::<Signals>();
-}
-
-pub fn mod_signals() -> Signals {
-    return *storage::get_mut::<Signals>();
-}
-
-
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
+    pub seed: u64,
     pub world_w: i32,
     pub world_h: i32,
     pub quant_min_num: usize,
-    pub quant_init_num: usize,
-    pub quant_speed: f32,
-    pub quant_rotate: f32,
-    pub quant_size_min: i32,
-    pub quant_size_max: i32,
-    pub force: f32,
+    pub bond_strength: f32,
+    pub bond_max_extension: f32,
+    pub bond_activation: f32,
+    pub boundary: BoundaryMode,
+    pub gravity_mode: GravityMode,
+    /// Barnes–Hut opening angle, smaller is more accurate and slower.
+    pub grav_theta: f32,
+    /// Uniform acceleration applied to all bodies by rapier.
+    pub gravity: [f32; 2],
+    /// Physics time step in seconds.
+    pub dt: f32,
+    pub solver_iterations: usize,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            seed: 0,
             world_w: WORLD_W as i32,
             world_h: WORLD_H as i32,
-            quant_init_num: 2,
             quant_min_num: 2,
-            quant_rotate: 2.0,
-            quant_speed: 100.0,
-            quant_size_min: 12,
-            quant_size_max: 12,
-            force: 500.0,
+            bond_strength: 200000.0,
+            bond_max_extension: 20.0,
+            bond_activation: 40.0,
+            boundary: BoundaryMode::Walls,
+            gravity_mode: GravityMode::Off,
+            grav_theta: 0.5,
+            gravity: [0.0, 0.0],
+            dt: 1.0 / 60.0,
+            solver_iterations: 4,
        }
     }
 }
 
-#[derive(Clone, Copy)]
+impl Settings {
+
+    /// Saves settings as JSON preset.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        return fs::write(path, json);
+    }
+
+    /// Sets one field by its JSON name, e.g. `set_field("bond_strength", "8000")` or `set_field("boundary", "Periodic")`.
+    /// Value is parsed as JSON, and taken as a string if that fails.
+    pub fn set_field(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let mut json = serde_json::to_value(*self).map_err(|e| e.to_string())?;
+        let fields = match json.as_object_mut() {
+            Some(fields) => fields,
+            None => return Err(String::from("settings are not a JSON object")),
+        };
+        if !fields.contains_key(name) {
+            return Err(format!("unknown setting '{}'", name));
+        }
+        let value = serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        fields.insert(name.to_string(), value);
+        *self = serde_json::from_value(json).map_err(|e| format!("bad value for '{}': {}", name, e))?;
+        return Ok(());
+    }
+
+    /// Loads JSON preset, fields missing in the file keep default values and unknown ones are ignored.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let settings: Settings = serde_json::from_str(&json)?;
+        return Ok(settings);
+    }
+}
+
+/// What happens to quants at the edges of the world rectangle.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    /// Static colliders along the world edges.
+    Walls,
+    /// Molecules leaving one edge come back from the opposite one.
+    /// Forces use the nearest periodic image, but rapier doesn't detect collisions across the seam.
+    Periodic,
+    /// No boundary, quants can drift away.
+    Open,
+}
+
+impl BoundaryMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoundaryMode::Walls => "Walls",
+            BoundaryMode::Periodic => "Periodic",
+            BoundaryMode::Open => "Open",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
 pub struct Signals {
     pub add_single_quant: bool,
     pub add_some_quants: bool,
     pub add_hex_quant: bool,
     pub add_state_matter: bool,
     pub reset_all: bool,
+    pub save_snapshot: bool,
+    pub load_snapshot: bool,
+    pub spawn_element: Option<usize>,
+    pub spawn_molecule: Option<usize>,
+    pub save_template: bool,
 }
-
-impl Default for Signals {
-    fn default() -> Self {
-        Self {
-            add_single_quant: false,
-            add_some_quants: false,
-            add_hex_quant: false,
-            add_state_matter: false,
-            reset_all: false,
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,138 @@
+#![allow(unused)]
+
+use glam::Vec2;
+
+
+/// Maximum number of cells per point; for sparse, widely spread points cells are enlarged instead.
+const MAX_CELLS_PER_POINT: usize = 4;
+
+/// Uniform grid cell list for short-range neighbor search.
+/// Points are bucketed with counting sort, so building is O(n) and a query only visits the 3x3 cells
+/// around a position. Cell size must be at least the interaction range.
+pub struct CellGrid {
+    cell_size: Vec2,
+    origin: Vec2,
+    cols: usize,
+    rows: usize,
+    /// Neighbor cells wrap around the edges in periodic world.
+    periodic: bool,
+    cell_start: Vec<usize>,
+    items: Vec<usize>,
+}
+
+impl CellGrid {
+
+    pub fn new(points: &[Vec2], cell_size: f32) -> Self {
+        let mut min = Vec2::splat(f32::INFINITY);
+        let mut max = Vec2::splat(f32::NEG_INFINITY);
+        for p in points.iter() {
+            min = min.min(*p);
+            max = max.max(*p);
+        }
+        if points.is_empty() {
+            min = Vec2::ZERO;
+            max = Vec2::ZERO;
+        }
+        let extent = max - min;
+        let mut cell_size = cell_size.max(f32::EPSILON);
+        let max_cells = (points.len() * MAX_CELLS_PER_POINT).max(1);
+        while ((extent.x / cell_size) as usize + 1).saturating_mul((extent.y / cell_size) as usize + 1) > max_cells {
+            cell_size *= 2.0;
+        }
+        let cols = (extent.x / cell_size) as usize + 1;
+        let rows = (extent.y / cell_size) as usize + 1;
+        return Self::build(points, min, Vec2::splat(cell_size), cols, rows, false);
+    }
+
+    /// Grid covering periodic world `[0, size)`, positions outside are wrapped into it.
+    pub fn new_periodic(points: &[Vec2], cell_size: f32, size: Vec2) -> Self {
+        let max_cells = (points.len() * MAX_CELLS_PER_POINT).max(1);
+        // a tiny cell size would ask for more cells per row than there may be in total
+        let mut cols = ((size.x / cell_size.max(f32::EPSILON)) as usize).clamp(1, max_cells);
+        let mut rows = ((size.y / cell_size.max(f32::EPSILON)) as usize).clamp(1, max_cells);
+        while cols.saturating_mul(rows) > max_cells && (cols > 1 || rows > 1) {
+            cols = (cols / 2).max(1);
+            rows = (rows / 2).max(1);
+        }
+        let cell_size = size / Vec2::new(cols as f32, rows as f32);
+        return Self::build(points, Vec2::ZERO, cell_size, cols, rows, true);
+    }
+
+    fn build(points: &[Vec2], origin: Vec2, cell_size: Vec2, cols: usize, rows: usize, periodic: bool) -> Self {
+        let mut grid = Self {
+            cell_size,
+            origin,
+            cols,
+            rows,
+            periodic,
+            cell_start: vec![0; cols * rows + 1],
+            items: vec![0; points.len()],
+        };
+        let cells: Vec<usize> = points.iter().map(|p| grid.cell_index(*p)).collect();
+        for c in cells.iter() {
+            grid.cell_start[*c + 1] += 1;
+        }
+        for c in 0..cols * rows {
+            grid.cell_start[c + 1] += grid.cell_start[c];
+        }
+        let mut fill = grid.cell_start.clone();
+        for (i, c) in cells.iter().enumerate() {
+            grid.items[fill[*c]] = i;
+            fill[*c] += 1;
+        }
+        return grid;
+    }
+
+    pub fn get_cell_size(&self) -> Vec2 {
+        return self.cell_size;
+    }
+
+    fn cell_coords(&self, pos: Vec2) -> (i64, i64) {
+        let rel = (pos - self.origin) / self.cell_size;
+        return (rel.x.floor() as i64, rel.y.floor() as i64);
+    }
+
+    fn wrap_coord(&self, c: i64, n: usize) -> usize {
+        if self.periodic {
+            return c.rem_euclid(n as i64) as usize;
+        }
+        return c.clamp(0, n as i64 - 1) as usize;
+    }
+
+    fn cell_index(&self, pos: Vec2) -> usize {
+        let (x, y) = self.cell_coords(pos);
+        return self.wrap_coord(y, self.rows) * self.cols + self.wrap_coord(x, self.cols);
+    }
+
+    /// Distinct cells in row or column `c - 1..=c + 1`, returned as array and count.
+    fn neighbor_coords(&self, c: i64, n: usize) -> ([usize; 3], usize) {
+        let mut coords = [0; 3];
+        let mut num = 0;
+        for i in c - 1..=c + 1 {
+            if !self.periodic && (i < 0 || i >= n as i64) {
+                continue;
+            }
+            let i = self.wrap_coord(i, n);
+            if !coords[..num].contains(&i) {
+                coords[num] = i;
+                num += 1;
+            }
+        }
+        return (coords, num);
+    }
+
+    /// Calls `f` with index of every point in the 3x3 cells around `pos`.
+    pub fn for_each_neighbor(&self, pos: Vec2, mut f: impl FnMut(usize)) {
+        let (cx, cy) = self.cell_coords(pos);
+        let (xs, xs_num) = self.neighbor_coords(cx, self.cols);
+        let (ys, ys_num) = self.neighbor_coords(cy, self.rows);
+        for y in ys[..ys_num].iter() {
+            for x in xs[..xs_num].iter() {
+                let c = y * self.cols + x;
+                for item in self.items[self.cell_start[c]..self.cell_start[c + 1]].iter() {
+                    f(*item);
+                }
+            }
+        }
+    }
+}
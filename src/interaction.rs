@@ -0,0 +1,141 @@
+#![allow(unused)]
+
+use quad_rand::RandGenerator;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Falloff {
+    /// Full strength in the whole range.
+    Constant,
+    /// Strength decreases linearly to zero at the end of range.
+    Linear,
+    /// Strength decreases with square of distance to zero at the end of range.
+    Quadratic,
+}
+
+impl Falloff {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Falloff::Constant => "Constant",
+            Falloff::Linear => "Linear",
+            Falloff::Quadratic => "Quadratic",
+        }
+    }
+}
+
+/// Force acting between two species. Positive strength attracts, negative repels.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ForceLaw {
+    pub strength: f32,
+    pub range: f32,
+    pub falloff: Falloff,
+}
+
+impl Default for ForceLaw {
+    fn default() -> Self {
+        Self { strength: 0.0, range: 80.0, falloff: Falloff::Linear }
+    }
+}
+
+impl ForceLaw {
+
+    /// Law exerts force somewhere, a zero range reaches nothing.
+    pub fn is_active(&self) -> bool {
+        return self.strength != 0.0 && self.range > 0.0;
+    }
+
+    /// Returns force magnitude at given distance, zero outside of range.
+    pub fn get_force(&self, dist: f32) -> f32 {
+        if dist >= self.range || self.strength == 0.0 {
+            return 0.0;
+        }
+        let x = dist / self.range;
+        return match self.falloff {
+            Falloff::Constant => self.strength,
+            Falloff::Linear => self.strength * (1.0 - x),
+            Falloff::Quadratic => self.strength * (1.0 - x) * (1.0 - x),
+        };
+    }
+}
+
+/// Per species-pair force laws. Law at `[i, j]` is the force acting on species `i` from species `j`,
+/// so the matrix doesn't have to be symmetric.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InteractionMatrix {
+    size: usize,
+    laws: Vec<ForceLaw>,
+}
+
+impl Default for InteractionMatrix {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl InteractionMatrix {
+
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            laws: vec![ForceLaw::default(); size * size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        return self.size;
+    }
+
+    /// Changes number of species, keeping laws of species which are still present.
+    pub fn resize(&mut self, size: usize) {
+        if size == self.size {
+            return;
+        }
+        let mut laws = vec![ForceLaw::default(); size * size];
+        for i in 0..size.min(self.size) {
+            for j in 0..size.min(self.size) {
+                laws[i * size + j] = self.laws[i * self.size + j];
+            }
+        }
+        self.size = size;
+        self.laws = laws;
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<&ForceLaw> {
+        if i >= self.size || j >= self.size {
+            return None;
+        }
+        return self.laws.get(i * self.size + j);
+    }
+
+    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut ForceLaw> {
+        if i >= self.size || j >= self.size {
+            return None;
+        }
+        return self.laws.get_mut(i * self.size + j);
+    }
+
+    /// Longest range of all laws with non-zero strength and range.
+    pub fn max_range(&self) -> f32 {
+        let mut range: f32 = 0.0;
+        for law in self.laws.iter().filter(|law| law.is_active()) {
+            range = range.max(law.range);
+        }
+        return range;
+    }
+
+    pub fn is_active(&self) -> bool {
+        return self.laws.iter().any(|law| law.is_active());
+    }
+
+    pub fn clear(&mut self) {
+        self.laws = vec![ForceLaw::default(); self.size * self.size];
+    }
+
+    /// Sets random strength for every pair of species, keeping ranges and falloffs.
+    pub fn randomize(&mut self, rng: &RandGenerator, max_strength: f32) {
+        for law in self.laws.iter_mut() {
+            law.strength = rng.gen_range(-max_strength, max_strength);
+        }
+    }
+}
@@ -5,67 +5,124 @@ use std::f32::consts::PI;
 use crate::util::*;
 use crate::physics::*;
 use crate::globals::*;
-use macroquad::{color, prelude::*};
-use macroquad::rand::*;
+use glam::Vec2;
+use crate::color::{self, *};
+use quad_rand::*;
 use rapier2d::geometry::*;
 use rapier2d::na::Point2;
 use rapier2d::na::Vector2;
-use rapier2d::prelude::ImpulseJointHandle;
+use rapier2d::prelude::{GenericJoint, ImpulseJointHandle, JointAxis};
 use rapier2d::prelude::{RigidBody, RigidBodyHandle};
 
 
 pub struct MyJoint {
     pub color: color::Color,
     pub physics_handle: ImpulseJointHandle,
+    /// Max impulse the bond can carry in one step before it breaks.
+    pub strength: f32,
+    /// Max change of distance between bodies before the bond breaks.
+    pub max_extension: f32,
+    pub rest_length: f32,
     pos1: Vec2,
     pos2: Vec2,
+    prev_pos1: Vec2,
+    prev_pos2: Vec2,
 }
 
 
 
 impl MyJoint {
     
-    pub fn new(body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, anchors: (Point2<f32>, Point2<f32>), physics: &mut Physics) -> Self {
-        let color = random_color();
+    pub fn new(body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, anchors: (Point2<f32>, Point2<f32>), rng: &RandGenerator, physics: &mut Physics) -> Self {
+        let color = random_color(rng);
         let handle = physics.add_prismatic_joint(body_handle1, body_handle2, anchors);
+        let pos1 = physics.get_object_position(body_handle1).unwrap_or_default();
+        let pos2 = physics.get_object_position(body_handle2).unwrap_or_default();
         Self {
             color,
             physics_handle: handle,
-            pos1: Vec2::ZERO,
-            pos2: Vec2::ZERO,
+            strength: f32::INFINITY,
+            max_extension: f32::INFINITY,
+            rest_length: body_distance(body_handle1, body_handle2, physics),
+            pos1,
+            pos2,
+            prev_pos1: pos1,
+            prev_pos2: pos2,
         }
     }
 
-    pub fn draw(&self) {
-        draw_line(self.pos1.x, self.pos1.y, self.pos2.x, self.pos2.y, 3.0, self.color);
-    }    
+    pub fn new_custom(body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, joint: GenericJoint, color: Color, strength: f32, max_extension: f32, physics: &mut Physics) -> Self {
+        let handle = physics.add_joint(body_handle1, body_handle2, joint);
+        let pos1 = physics.get_object_position(body_handle1).unwrap_or_default();
+        let pos2 = physics.get_object_position(body_handle2).unwrap_or_default();
+        Self {
+            color,
+            physics_handle: handle,
+            strength,
+            max_extension,
+            rest_length: body_distance(body_handle1, body_handle2, physics),
+            pos1,
+            pos2,
+            prev_pos1: pos1,
+            prev_pos2: pos2,
+        }
+    }
+
+    /// Two halves of the bond to draw, each from its quant to the middle; in periodic world
+    /// each half goes towards the nearest image of the other quant.
+    pub fn get_draw_segments(&self, alpha: f32, period: Option<Vec2>) -> [(Vec2, Vec2); 2] {
+        let pos1 = lerp_position(self.prev_pos1, self.pos1, alpha, period);
+        let pos2 = lerp_position(self.prev_pos2, self.pos2, alpha, period);
+        let d = match period {
+            Some(size) => min_image(pos2 - pos1, size),
+            None => pos2 - pos1,
+        };
+        return [(pos1, pos1 + d / 2.0), (pos2, pos2 - d / 2.0)];
+    }
 
     pub fn update(&mut self, physics: &mut Physics) {
+        self.prev_pos1 = self.pos1;
+        self.prev_pos2 = self.pos2;
+        if let Some(joint) = physics.impulse_joint_set.get(self.physics_handle) {
+            if let Some(rb1) = physics.rigid_bodies.get(joint.body1) {
+                let (pos1, rot1) = iso_to_vec2_rot(rb1.position());
+                self.pos1 = pos1;
+            }
+            if let Some(rb2) = physics.rigid_bodies.get(joint.body2) {
+                let (pos2, rot2) = iso_to_vec2_rot(rb2.position());
+                self.pos2 = pos2;
+            }
+        }
+    }
+
+    /// Returns impulse carried by the joint in the last step and current extension from rest length.
+    pub fn get_strain(&self, physics: &Physics) -> (f32, f32) {
         match physics.impulse_joint_set.get(self.physics_handle) {
             Some(joint) => {
-                match physics.rigid_bodies.get(joint.body1) {
-                    Some(rb1) => {
-                        let (pos1, rot1) = iso_to_vec2_rot(rb1.position());
-                        self.pos1 = pos1;
-                    },
-                    None => {
-
-                    },
-                }
-                match physics.rigid_bodies.get(joint.body2) {
-                    Some(rb2) => {
-                        let (pos2, rot2) = iso_to_vec2_rot(rb2.position());
-                        self.pos2 = pos2;
-                    },
-                    None => {
-                        
-                    }
-                }
+                let limit_impulse = match joint.data.limits(JointAxis::X) {
+                    Some(limits) => limits.impulse,
+                    None => 0.0,
+                };
+                let impulse = Vector2::new(joint.impulses.x + limit_impulse, joint.impulses.y).norm();
+                let extension = (body_distance(joint.body1, joint.body2, physics) - self.rest_length).abs();
+                return (impulse, extension);
             },
             None => {
-
+                return (0.0, 0.0);
             },
         }
     }
 
-}
\ No newline at end of file
+    pub fn is_overloaded(&self, physics: &Physics) -> bool {
+        let (impulse, extension) = self.get_strain(physics);
+        return impulse > self.strength || extension > self.max_extension;
+    }
+
+}
+
+fn body_distance(body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, physics: &Physics) -> f32 {
+    match (physics.get_object_position(body_handle1), physics.get_object_position(body_handle2)) {
+        (Some(p1), Some(p2)) => p1.distance(p2),
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,49 @@
+#![allow(clippy::needless_return)]
+
+//! Windowless core of QUANTUM: 2D particle sandbox where quants of different elements
+//! interact, bond into molecules and react.
+//!
+//! Everything goes through [`world::World`]:
+//!
+//! ```no_run
+//! use quantum::globals::Settings;
+//! use quantum::molecule::MoleculeTemplate;
+//! use quantum::world::World;
+//! use glam::Vec2;
+//!
+//! let mut world = World::new(Settings { seed: 7, ..Default::default() });
+//! let quant = world.add_quant_at(Vec2::new(100.0, 100.0), 0).unwrap();
+//! let chain = MoleculeTemplate::chain("chain", &["G", "B", "G"], 14.0);
+//! let atoms = world.spawn_molecule(&chain, Vec2::new(300.0, 300.0), 0.0).unwrap();
+//! for _ in 0..600 {
+//!     world.step();
+//! }
+//! let stats = world.get_stats();
+//! println!("{:.1}s: {} quants in {} molecules", stats.sim_time, stats.quants, stats.molecules);
+//! ```
+//!
+//! The library doesn't use macroquad, so `quantum-cli` and other headless users link without
+//! window or audio system libraries. The macroquad application in `main.rs` is one consumer of this API
+//! and needs the `gui` feature (on by default); turning default features off skips building macroquad and egui.
+
+pub mod globals;
+pub mod color;
+pub mod util;
+pub mod physics;
+pub mod collector;
+pub mod quant;
+pub mod joint;
+pub mod world;
+pub mod snapshot;
+pub mod element;
+pub mod interaction;
+pub mod grid;
+pub mod quadtree;
+pub mod matter;
+pub mod molecule;
+pub mod tracker;
+pub mod census;
+pub mod timing;
+pub mod scenario;
+pub mod runner;
+pub mod sweep;
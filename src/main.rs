@@ -1,18 +1,16 @@
+#![allow(clippy::needless_return)]
 //#![allow(unused)]
 
 mod camera;
+mod draw;
 mod sim;
-mod util;
-mod physics;
-mod collector;
-mod globals;
-mod quant;
-mod joint;
+mod storage;
 mod ui;
 
 use macroquad::prelude::*;
 use crate::sim::*;
-use crate::globals::*;
+use crate::storage::*;
+use quantum::globals::*;
 
 
 fn app_configuration() -> Conf {
@@ -27,22 +25,27 @@ fn app_configuration() -> Conf {
 }
 
 fn setup() {
-    init_global_settings(Settings::default());
+    let settings = Settings {
+        seed: (macroquad::miniquad::date::now() * 1000.0) as u64,
+        ..Default::default()
+    };
+    println!("[SEED]: {}", settings.seed);
+    init_global_settings(settings);
     init_global_signals(Signals::default());
 }
 
-#[macroquad::main(app_configuration)]
-async fn main() {
+fn main() {
+    macroquad::Window::from_config(app_configuration(), run_app());
+}
+
+async fn run_app() {
     setup();
     let font = load_ttf_font("assets/fonts/firacode.ttf").await.expect("can't load font resource!");
-    let mut sim = Simulation::new(font.clone());
-    sim.init();
+    let mut sim = Simulation::new(font);
     loop {
         sim.input();
-        if sim.is_running() {
-            sim.update();
-            sim.draw();
-        }
+        sim.update();
+        sim.draw();
         next_frame().await;
     }
 }
@@ -0,0 +1,157 @@
+#![allow(unused)]
+
+use glam::Vec2;
+use crate::util::Rect;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatterState {
+    /// Crystal lattice of bonded quants.
+    Solid,
+    /// Unbonded quants held together by short-range cohesion.
+    Liquid,
+    /// Unbonded quants with random thermal velocities.
+    Gas,
+}
+
+impl MatterState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MatterState::Solid => "Solid",
+            MatterState::Liquid => "Liquid",
+            MatterState::Gas => "Gas",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Lattice {
+    Square,
+    Hexagonal,
+}
+
+impl Lattice {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lattice::Square => "Square",
+            Lattice::Hexagonal => "Hexagonal",
+        }
+    }
+
+    /// Number of nearest neighbors of a point inside the lattice.
+    pub fn coordination(&self) -> usize {
+        match self {
+            Lattice::Square => return 4,
+            Lattice::Hexagonal => return 6,
+        }
+    }
+
+    /// Distance between rows as part of spacing.
+    fn row_factor(&self) -> f32 {
+        match self {
+            Lattice::Square => return 1.0,
+            Lattice::Hexagonal => return 3.0_f32.sqrt() / 2.0,
+        }
+    }
+
+    fn row_offset(&self, row: usize, spacing: f32) -> f32 {
+        match self {
+            Lattice::Hexagonal if row % 2 == 1 => return spacing / 2.0,
+            _ => return 0.0,
+        }
+    }
+}
+
+/// Parameters of a state-of-matter generator: `count` quants of `element` spread over `region`.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatterSpec {
+    pub state: MatterState,
+    /// Arrangement of solid; liquid and gas always use square one.
+    pub lattice: Lattice,
+    pub element: usize,
+    pub count: usize,
+    pub region: Rect,
+    /// Standard deviation of initial velocity components, in px/s.
+    pub temperature: f32,
+    /// Strength of attraction between quants of this liquid, other quants of the element don't feel it.
+    pub cohesion: f32,
+}
+
+impl MatterSpec {
+
+    /// Lattice actually used for the state.
+    pub fn get_lattice(&self) -> Lattice {
+        match self.state {
+            MatterState::Solid => return self.lattice,
+            _ => return Lattice::Square,
+        }
+    }
+
+    /// Lattice neighbors a solid quant of given valence can't bond with, `None` if it bonds with all.
+    pub fn get_missing_bonds(&self, valence: usize) -> Option<usize> {
+        let coordination = self.lattice.coordination();
+        if self.state != MatterState::Solid || valence >= coordination {
+            return None;
+        }
+        return Some(coordination - valence);
+    }
+
+    /// Most quants of given radius the region can hold.
+    pub fn get_capacity(&self, radius: f32) -> usize {
+        return region_capacity(self.region, 2.0 * radius, self.get_lattice());
+    }
+}
+
+impl Default for MatterSpec {
+    fn default() -> Self {
+        Self {
+            state: MatterState::Solid,
+            lattice: Lattice::Hexagonal,
+            element: 0,
+            count: 100,
+            region: Rect::new(300.0, 200.0, 300.0, 300.0),
+            temperature: 100.0,
+            cohesion: 5000.0,
+        }
+    }
+}
+
+/// Number of points in every row of the lattice with given spacing that fits inside `region`.
+fn lattice_rows(region: Rect, spacing: f32, lattice: Lattice) -> Vec<usize> {
+    let row_height = spacing * lattice.row_factor();
+    let rows = (region.h / row_height) as usize;
+    return (0..rows).map(|row| ((region.w - lattice.row_offset(row, spacing)) / spacing) as usize).collect();
+}
+
+/// Most points `fill_region` can place in `region` at `min_spacing`.
+pub fn region_capacity(region: Rect, min_spacing: f32, lattice: Lattice) -> usize {
+    return lattice_rows(region, min_spacing, lattice).iter().sum();
+}
+
+/// Places up to `count` points row by row in `region`, evenly spread but at least `min_spacing` apart.
+/// Hexagonal arrangement shifts every second row by half of spacing. All points stay inside the region,
+/// so fewer than `count` are returned when it can't hold them. Returns points and the spacing used.
+pub fn fill_region(region: Rect, count: usize, min_spacing: f32, lattice: Lattice) -> (Vec<Vec2>, f32) {
+    let area = region.w * region.h;
+    let mut spacing = (area / (count.max(1) as f32 * lattice.row_factor())).sqrt().max(min_spacing);
+    // whole rows and columns hold a bit less than the area suggests, so spacing shrinks until they fit
+    while spacing > min_spacing && lattice_rows(region, spacing, lattice).iter().sum::<usize>() < count {
+        spacing = (spacing * 0.98).max(min_spacing);
+    }
+    let row_height = spacing * lattice.row_factor();
+    let mut points: Vec<Vec2> = Vec::with_capacity(count);
+    for (row, cols) in lattice_rows(region, spacing, lattice).into_iter().enumerate() {
+        let offset = lattice.row_offset(row, spacing);
+        for col in 0..cols {
+            if points.len() >= count {
+                return (points, spacing);
+            }
+            let x = region.x + spacing / 2.0 + offset + col as f32 * spacing;
+            let y = region.y + row_height / 2.0 + row as f32 * row_height;
+            points.push(Vec2::new(x, y));
+        }
+    }
+    return (points, spacing);
+}
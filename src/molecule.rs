@@ -0,0 +1,255 @@
+#![allow(unused)]
+
+use std::f32::consts::PI;
+use glam::Vec2;
+use rapier2d::na::{Point2, Vector2};
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use crate::collector::ElementsBox;
+
+
+/// Atoms closer than this part of sum of their radii are treated as overlapping.
+/// Colliders are only 0.75 or 0.9 of the radius and touching atoms press slightly into each other,
+/// so the margin is wide enough for molecules saved from the running world.
+const MIN_ATOM_DISTANCE: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BondType {
+    /// Rigid bond, keeps both distance and angle.
+    Fixed,
+    /// Bond along the line between atoms, its length can change within limits.
+    Prismatic,
+    /// Atoms can rotate around the bond anchor, limits are angles.
+    Revolute,
+}
+
+impl BondType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BondType::Fixed => "Fixed",
+            BondType::Prismatic => "Prismatic",
+            BondType::Revolute => "Revolute",
+        }
+    }
+}
+
+/// Problem found in molecule template by `MoleculeTemplate::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateError {
+    Empty,
+    NotFound { id: usize },
+    UnknownElement { atom: usize, symbol: String },
+    BadBond { bond: usize, i: usize, j: usize },
+    DuplicateBond { bond: usize, i: usize, j: usize },
+    ValenceExceeded { atom: usize, symbol: String, bonds: usize, valence: usize },
+    Overlap { atom1: usize, atom2: usize, distance: f32 },
+    BadName { name: String },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Empty => write!(f, "template has no atoms"),
+            TemplateError::NotFound { id } => write!(f, "no template with id {} in library", id),
+            TemplateError::UnknownElement { atom, symbol } => write!(f, "atom {} uses unknown element '{}'", atom, symbol),
+            TemplateError::BadBond { bond, i, j } => write!(f, "bond {} joins atoms {} and {} which don't exist or are the same", bond, i, j),
+            TemplateError::DuplicateBond { bond, i, j } => write!(f, "bond {} joins atoms {} and {} which are already bonded", bond, i, j),
+            TemplateError::ValenceExceeded { atom, symbol, bonds, valence } => write!(f, "atom {} ({}) has {} bonds but valence {}", atom, symbol, bonds, valence),
+            TemplateError::Overlap { atom1, atom2, distance } => write!(f, "atoms {} and {} overlap (distance {:.1})", atom1, atom2, distance),
+            TemplateError::BadName { name } => write!(f, "'{}' can't be used as file name", name),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AtomTemplate {
+    /// Symbol of element in `ElementsBox`.
+    pub element: String,
+    /// Position relative to molecule origin.
+    pub position: [f32; 2],
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BondTemplate {
+    pub i: usize,
+    pub j: usize,
+    pub joint: BondType,
+    /// Distance kept by the bond, distance between the atoms if not set.
+    #[serde(default)]
+    pub rest_length: Option<f32>,
+    #[serde(default)]
+    pub limits: Option<[f32; 2]>,
+}
+
+/// Graph description of a molecule: atoms with relative positions and bonds between them.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MoleculeTemplate {
+    pub name: String,
+    pub atoms: Vec<AtomTemplate>,
+    pub bonds: Vec<BondTemplate>,
+}
+
+impl MoleculeTemplate {
+
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), atoms: vec![], bonds: vec![] }
+    }
+
+    /// Adds atom and returns its index.
+    pub fn add_atom(&mut self, element: &str, position: Vec2) -> usize {
+        self.atoms.push(AtomTemplate { element: element.to_string(), position: [position.x, position.y] });
+        return self.atoms.len() - 1;
+    }
+
+    pub fn add_bond(&mut self, i: usize, j: usize, joint: BondType, limits: Option<[f32; 2]>) {
+        self.bonds.push(BondTemplate { i, j, joint, rest_length: None, limits });
+    }
+
+    /// Center atom bonded with `n` atoms placed evenly around it.
+    pub fn star(name: &str, n: usize, center: &str, arm: &str, arm_length: f32) -> Self {
+        let mut template = Self::new(name);
+        let c = template.add_atom(center, Vec2::ZERO);
+        let step = 2.0 * PI / n as f32;
+        for k in 0..n {
+            let a = template.add_atom(arm, Vec2::from_angle(k as f32 * step) * arm_length);
+            template.add_bond(c, a, BondType::Prismatic, Some([0.0, 1.0]));
+        }
+        return template;
+    }
+
+    /// Straight chain of atoms, bonded one after another.
+    pub fn chain(name: &str, elements: &[&str], spacing: f32) -> Self {
+        let mut template = Self::new(name);
+        let offset = (elements.len() as f32 - 1.0) * spacing / 2.0;
+        for (k, element) in elements.iter().enumerate() {
+            let i = template.add_atom(element, Vec2::new(k as f32 * spacing - offset, 0.0));
+            if k > 0 {
+                template.add_bond(i - 1, i, BondType::Revolute, None);
+            }
+        }
+        return template;
+    }
+
+    /// Closed ring of `n` atoms with given distance between neighbors.
+    pub fn ring(name: &str, n: usize, element: &str, spacing: f32) -> Self {
+        let mut template = Self::new(name);
+        let radius = spacing / (2.0 * (PI / n as f32).sin());
+        let step = 2.0 * PI / n as f32;
+        for k in 0..n {
+            template.add_atom(element, Vec2::from_angle(k as f32 * step) * radius);
+        }
+        for k in 0..n {
+            template.add_bond(k, (k + 1) % n, BondType::Revolute, None);
+        }
+        return template;
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let template: MoleculeTemplate = serde_json::from_str(&json)?;
+        return Ok(template);
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        return Ok(());
+    }
+
+    /// Path of the template file in `dir`, named after the template.
+    /// Empty names and names that could point outside of `dir` are rejected.
+    pub fn file_path(&self, dir: &str) -> Result<String, TemplateError> {
+        let name = &self.name;
+        if name.trim().is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            return Err(TemplateError::BadName { name: name.clone() });
+        }
+        return Ok(format!("{}/{}.json", dir, name));
+    }
+
+    /// Checks template against element table and returns element ids of atoms.
+    pub fn validate(&self, elements: &ElementsBox) -> Result<Vec<usize>, TemplateError> {
+        if self.atoms.is_empty() {
+            return Err(TemplateError::Empty);
+        }
+        let mut element_ids: Vec<usize> = Vec::with_capacity(self.atoms.len());
+        for (atom, a) in self.atoms.iter().enumerate() {
+            match elements.find(&a.element) {
+                Some(id) => element_ids.push(id),
+                None => return Err(TemplateError::UnknownElement { atom, symbol: a.element.clone() }),
+            }
+        }
+        let mut bonds_num = vec![0; self.atoms.len()];
+        let mut pairs: Vec<(usize, usize)> = Vec::with_capacity(self.bonds.len());
+        for (bond, b) in self.bonds.iter().enumerate() {
+            if b.i >= self.atoms.len() || b.j >= self.atoms.len() || b.i == b.j {
+                return Err(TemplateError::BadBond { bond, i: b.i, j: b.j });
+            }
+            let pair = (b.i.min(b.j), b.i.max(b.j));
+            if pairs.contains(&pair) {
+                return Err(TemplateError::DuplicateBond { bond, i: b.i, j: b.j });
+            }
+            pairs.push(pair);
+            bonds_num[b.i] += 1;
+            bonds_num[b.j] += 1;
+        }
+        let radii: Vec<f32> = element_ids.iter().map(|id| elements.get(*id).map(|e| e.radius).unwrap_or(0.0)).collect();
+        for atom in 0..self.atoms.len() {
+            if let Some(element) = elements.get(element_ids[atom]) {
+                if bonds_num[atom] > element.valence {
+                    return Err(TemplateError::ValenceExceeded { atom, symbol: element.symbol.clone(), bonds: bonds_num[atom], valence: element.valence });
+                }
+            }
+        }
+        let positions = self.get_positions(Vec2::ZERO, 0.0);
+        for atom1 in 0..positions.len() {
+            for atom2 in atom1 + 1..positions.len() {
+                let distance = positions[atom1].distance(positions[atom2]);
+                if distance < (radii[atom1] + radii[atom2]) * MIN_ATOM_DISTANCE {
+                    return Err(TemplateError::Overlap { atom1, atom2, distance });
+                }
+            }
+        }
+        return Ok(element_ids);
+    }
+
+    /// Atom positions moved to `position` and rotated by `rotation` around molecule origin.
+    pub fn get_positions(&self, position: Vec2, rotation: f32) -> Vec<Vec2> {
+        let rot = Vec2::from_angle(rotation);
+        return self.atoms.iter().map(|atom| position + rot.rotate(Vec2::new(atom.position[0], atom.position[1]))).collect();
+    }
+}
+
+impl BondTemplate {
+
+    /// Builds joint between atoms at given world positions, for bodies with zero rotation.
+    pub fn build_joint(&self, pos1: Vec2, pos2: Vec2) -> GenericJoint {
+        let d = pos2 - pos1;
+        let rest_length = self.rest_length.unwrap_or(d.length());
+        let vr = d.normalize_or_zero() * rest_length / 2.0;
+        let anchor1 = Point2::new(vr.x, vr.y);
+        let anchor2 = Point2::new(-vr.x, -vr.y);
+        match self.joint {
+            BondType::Fixed => {
+                return FixedJointBuilder::new().local_anchor1(anchor1).local_anchor2(anchor2).build().into();
+            },
+            BondType::Prismatic => {
+                let mut builder = PrismaticJointBuilder::new(UnitVector::new_normalize(Vector2::new(vr.x, vr.y)))
+                    .local_anchor1(anchor1).local_anchor2(anchor2);
+                if let Some(limits) = self.limits {
+                    builder = builder.limits(limits);
+                }
+                return builder.build().into();
+            },
+            BondType::Revolute => {
+                let mut builder = RevoluteJointBuilder::new().local_anchor1(anchor1).local_anchor2(anchor2);
+                if let Some(limits) = self.limits {
+                    builder = builder.limits(limits);
+                }
+                return builder.build().into();
+            },
+        }
+    }
+}
@@ -2,14 +2,57 @@
 
 use crate::util::*;
 use crate::globals::*;
-use macroquad::prelude::*;
+use crate::quadtree::QuadTree;
+use glam::Vec2;
 use rapier2d::na::Isometry2;
 use rapier2d::na::{Point2, Vector2};
 use rapier2d::prelude::*;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::f32::consts::PI;
-use crossbeam;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Why a physics query on a body couldn't be answered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhysicsError {
+    /// Body was removed, or the handle comes from another physics world.
+    StaleHandle(RigidBodyHandle),
+    /// Body exists but has no collider attached.
+    MissingCollider(RigidBodyHandle),
+    /// Query works only for some collider shapes, e.g. size of a ball.
+    UnsupportedShape(ShapeType),
+}
+
+impl fmt::Display for PhysicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhysicsError::StaleHandle(rbh) => write!(f, "no rigid body for handle {:?}", rbh.into_raw_parts()),
+            PhysicsError::MissingCollider(rbh) => write!(f, "rigid body {:?} has no collider", rbh.into_raw_parts()),
+            PhysicsError::UnsupportedShape(shape) => write!(f, "unsupported collider shape {:?}", shape),
+        }
+    }
+}
+
+/// Long-range attraction between all bodies.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GravityMode {
+    Off,
+    /// Exact pairwise sum, O(n²).
+    BruteForce,
+    /// Quadtree approximation, O(n log n).
+    BarnesHut,
+}
+
+impl GravityMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityMode::Off => "Off",
+            GravityMode::BruteForce => "Brute Force",
+            GravityMode::BarnesHut => "Barnes-Hut",
+        }
+    }
+}
 
 pub struct PhysicsProperities {
     pub friction: f32,
@@ -58,7 +101,14 @@ pub struct Physics {
     query_pipeline: QueryPipeline,
     physics_hooks: (),
     event_handler: (),
-    grav_time: f32,
+    /// Pairs of bodies which started touching during last step.
+    pub contacts: Vec<(RigidBodyHandle, RigidBodyHandle)>,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 impl Physics {
@@ -80,7 +130,7 @@ impl Physics {
             query_pipeline: QueryPipeline::new(),
             physics_hooks: (),
             event_handler: (),
-            grav_time: 0.0,
+            contacts: vec![],
         }
     }
 
@@ -109,44 +159,45 @@ impl Physics {
 
 
 
-/*         while let Ok(collision_event) = collision_recv.try_recv() {
+        self.contacts.clear();
+        while let Ok(collision_event) = collision_recv.try_recv() {
             match collision_event {
-                CollisionEvent::Started(_, _, CollisionEventFlags::SENSOR) => {
-                    println!("sensor");
-                },
-                CollisionEvent::Started(_, _, CollisionEventFlags::REMOVED) => {
-                    println!("removed");
-                },
+                CollisionEvent::Started(_, _, CollisionEventFlags::SENSOR) => {},
+                CollisionEvent::Started(_, _, CollisionEventFlags::REMOVED) => {},
                 CollisionEvent::Started(c1, c2, _) => {
-                    let collider1 = self.colliders.get(c1).unwrap();
-                    let collider2 = self.colliders.get(c2).unwrap();
-                    let body1 = self.rigid_bodies.get(collider1.parent().unwrap()).unwrap();
-                    let body2 = self.rigid_bodies.get(collider2.parent().unwrap()).unwrap();
-                    let eng = body1.kinetic_energy() + body2.kinetic_energy();
-                    let txt = match eng {
-                        e if e < 1000.0 => {
-                            format!("{}J", e.round())
-                        },
-                        e if e < 1000_000.0 => {
-                            format!("{}kJ", (e/1000.0).round())
-                        },
-                        e if e < 1000_000_000.0 => {
-                            format!("{}MJ", (e/1000_000.0).round())
-                        },
-                        e => {
-                            format!("{}GJ", (e/1000_000_000.0).round())
-                        },
-                    };
-                    println!("[COLLISION]: energy: {}", txt);
-                },
-                CollisionEvent::Stopped(_, _, _) => {
-
+                    let rbh1 = self.get_body_handle_from_collider(c1);
+                    let rbh2 = self.get_body_handle_from_collider(c2);
+                    if let (Some(rbh1), Some(rbh2)) = (rbh1, rbh2) {
+                        self.contacts.push((rbh1, rbh2));
+                    }
                 },
+                CollisionEvent::Stopped(_, _, _) => {},
             }
-            //println!("Received collision event: {:?}", collision_event);
-        } */
+        }
+    }
+
+    pub fn get_dt(&self) -> f32 {
+        return self.integration_parameters.dt;
+    }
+
+    pub fn set_dt(&mut self, dt: f32) {
+        self.integration_parameters.dt = dt;
+    }
+
+    pub fn get_solver_iterations(&self) -> usize {
+        return self.integration_parameters.max_velocity_iterations;
+    }
+
+    pub fn set_solver_iterations(&mut self, iterations: usize) {
+        self.integration_parameters.max_velocity_iterations = iterations.max(1);
+    }
+
+    pub fn get_gravity(&self) -> Vec2 {
+        return Vec2::new(self.gravity.x, self.gravity.y);
+    }
 
-        //self.update_grav();
+    pub fn set_gravity(&mut self, gravity: Vec2) {
+        self.gravity = Vector2::new(gravity.x, gravity.y);
     }
 
     pub fn remove_physics_object(&mut self, body_handle: RigidBodyHandle) {
@@ -159,11 +210,8 @@ impl Physics {
     }
 
     fn get_body_handle_from_collider(&self, collider_handle: ColliderHandle) -> Option<RigidBodyHandle> {
-        let collider: &Collider;
-        match self.colliders.get(collider_handle) {
-            Some(col) => {
-                collider = col;
-            }
+        let collider: &Collider = match self.colliders.get(collider_handle) {
+            Some(col) => col,
             None => {
                 return None;
             }
@@ -189,7 +237,6 @@ impl Physics {
         let iso = make_isometry(rel_position.x, rel_position.y, rotation);
         let collider = match shape.shape_type() {
             ShapeType::Ball => {
-                let radius = shape.0.as_ball().unwrap().radius;
                 ColliderBuilder::new(shape).position(iso).density(physics_props.density).friction(physics_props.friction).restitution(physics_props.restitution)
                     .active_collision_types(ActiveCollisionTypes::DYNAMIC_DYNAMIC).active_events(ActiveEvents::COLLISION_EVENTS).build()
             },
@@ -223,6 +270,24 @@ impl Physics {
         return rbh;
     }
 
+    /// Fixed body with four thin walls around `[0, size]` rectangle.
+    pub fn add_walls(&mut self, size: Vec2) -> RigidBodyHandle {
+        let rbh = self.rigid_bodies.insert(RigidBodyBuilder::fixed().build());
+        let t = WALL_THICKNESS / 2.0;
+        let walls = [
+            (Vec2::new(size.x / 2.0, -t), Vec2::new(size.x / 2.0 + WALL_THICKNESS, t)),
+            (Vec2::new(size.x / 2.0, size.y + t), Vec2::new(size.x / 2.0 + WALL_THICKNESS, t)),
+            (Vec2::new(-t, size.y / 2.0), Vec2::new(t, size.y / 2.0 + WALL_THICKNESS)),
+            (Vec2::new(size.x + t, size.y / 2.0), Vec2::new(t, size.y / 2.0 + WALL_THICKNESS)),
+        ];
+        for (center, half) in walls {
+            let collider = ColliderBuilder::cuboid(half.x, half.y).translation(vector![center.x, center.y])
+                .friction(0.0).restitution(1.0).build();
+            self.colliders.insert_with_parent(collider, rbh, &mut self.rigid_bodies);
+        }
+        return rbh;
+    }
+
     pub fn add_prismatic_joint(&mut self, body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, anchors: (Point2<f32>, Point2<f32>)) -> ImpulseJointHandle {
         let p1 = anchors.0;
         let p2 = anchors.1;
@@ -234,27 +299,36 @@ impl Physics {
         return joint_handle;
     }
 
-    pub fn get_physics_data(&self, handle: RigidBodyHandle) -> PhysicsData {
-        if let Some(rb) = self.rigid_bodies.get(handle) {
-            let iso = rb.position();
-            let (pos, rot) = iso_to_vec2_rot(iso);
-            let force = Vec2::new(rb.user_force().data.0[0][0], rb.user_force().data.0[0][1]);
-            let data = PhysicsData {
-                position: pos,
-                rotation: rot,
-                mass: rb.mass(),
-                kin_eng: Some(rb.kinetic_energy()),
-                force: Some(force),
-            };
-            return data;
-        } else {
-            return PhysicsData {
-                position: Vec2::new(WORLD_W / 2., WORLD_H / 2.),
-                rotation: 0.0,
-                mass: 0.0,
-                kin_eng: Some(0.0),
-                force: None,
-            };
+    pub fn add_joint(&mut self, body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, joint: impl Into<GenericJoint>) -> ImpulseJointHandle {
+        return self.impulse_joint_set.insert(body_handle1, body_handle2, joint, true);
+    }
+
+    pub fn get_physics_data(&self, handle: RigidBodyHandle) -> Result<PhysicsData, PhysicsError> {
+        let rb = self.get_body(handle)?;
+        let (pos, rot) = iso_to_vec2_rot(rb.position());
+        let force = Vec2::new(rb.user_force().data.0[0][0], rb.user_force().data.0[0][1]);
+        return Ok(PhysicsData {
+            position: pos,
+            rotation: rot,
+            mass: rb.mass(),
+            kin_eng: Some(rb.kinetic_energy()),
+            force: Some(force),
+        });
+    }
+
+    fn get_body(&self, handle: RigidBodyHandle) -> Result<&RigidBody, PhysicsError> {
+        match self.rigid_bodies.get(handle) {
+            Some(rb) => return Ok(rb),
+            None => return Err(PhysicsError::StaleHandle(handle)),
+        }
+    }
+
+    /// First collider of the body.
+    fn get_body_collider(&self, handle: RigidBodyHandle) -> Result<&Collider, PhysicsError> {
+        let rb = self.get_body(handle)?;
+        match rb.colliders().first().and_then(|colh| self.colliders.get(*colh)) {
+            Some(collider) => return Ok(collider),
+            None => return Err(PhysicsError::MissingCollider(handle)),
         }
     }
 
@@ -271,59 +345,69 @@ impl Physics {
         }
     }
 
-    pub fn get_object_size(&self, handle: RigidBodyHandle) -> Option<f32> {
-        let rb = self.rigid_bodies.get(handle);
-        match rb {
-            Some(body) => {
-                match body.colliders().first() {
-                    Some(colh) => {
-                        match self.colliders.get(*colh) {
-                            Some(collider) => {
-                                return Some(collider.shape().as_ball().unwrap().radius);
-                            },
-                            None => {
-                                return None;
-                            },
-                        }
-                    },
-                    None => { 
-                      return None; 
-                    },
-                }
-            },
-            None => {
-                return None;
-            },
+    /// Radius of the body's ball collider.
+    pub fn get_object_size(&self, handle: RigidBodyHandle) -> Result<f32, PhysicsError> {
+        let collider = self.get_body_collider(handle)?;
+        match collider.shape().as_ball() {
+            Some(ball) => return Ok(ball.radius),
+            None => return Err(PhysicsError::UnsupportedShape(collider.shape().shape_type())),
         }
     }
 
-    pub fn get_contacts_set(&mut self, agent_body_handle: RigidBodyHandle, radius: f32) -> HashSet<RigidBodyHandle> {
+    pub fn get_contacts_set(&self, agent_body_handle: RigidBodyHandle, radius: f32) -> Result<HashSet<RigidBodyHandle>, PhysicsError> {
         let mut contacts: HashSet<RigidBodyHandle> = HashSet::new();
-        let rb = self.rigid_bodies.get(agent_body_handle).unwrap();
+        let rb = self.get_body(agent_body_handle)?;
         let filter = QueryFilter {
             flags: QueryFilterFlags::ONLY_DYNAMIC | QueryFilterFlags::EXCLUDE_SENSORS,
             groups: None,
             exclude_rigid_body: Some(agent_body_handle),
             ..Default::default()
         };
+        if rb.colliders().is_empty() {
+            return Err(PhysicsError::MissingCollider(agent_body_handle));
+        }
         for c in rb.colliders() {
-            let collider = self.colliders.get(*c).unwrap();
-            if collider.is_sensor() {
-                continue;
+            match self.colliders.get(*c) {
+                Some(collider) if !collider.is_sensor() => {},
+                _ => continue,
             }
             self.query_pipeline.intersections_with_shape(&self.rigid_bodies, &self.colliders, rb.position(), &rapier2d::geometry::Ball::new(radius), filter,
                 |collided| {
-                    let rb2_handle = self.get_body_handle_from_collider(collided).unwrap();
-                    contacts.insert(rb2_handle);
+                    if let Some(rb2_handle) = self.get_body_handle_from_collider(collided) {
+                        contacts.insert(rb2_handle);
+                    }
                     return true;
                 },
             );
         }
-        return contacts;
+        return Ok(contacts);
+    }
+
+    /// Brings the query structure up to date with bodies added or moved since the last step.
+    pub fn update_queries(&mut self) {
+        self.query_pipeline.update(&self.rigid_bodies, &self.colliders);
+    }
+
+    /// Dynamic body whose collider contains the point, if any.
+    /// Bodies changed since the last step are only seen after `update_queries`.
+    pub fn pick_body(&self, point: Vec2) -> Option<RigidBodyHandle> {
+        let mut picked: Option<RigidBodyHandle> = None;
+        let filter = QueryFilter {
+            flags: QueryFilterFlags::ONLY_DYNAMIC | QueryFilterFlags::EXCLUDE_SENSORS,
+            ..Default::default()
+        };
+        self.query_pipeline.intersections_with_point(&self.rigid_bodies, &self.colliders, &Point2::new(point.x, point.y), filter,
+            |collided| {
+                picked = self.get_body_handle_from_collider(collided);
+                return picked.is_none();
+            },
+        );
+        return picked;
     }
 
-    pub fn get_closed_agent(&self, agent_body_handle: RigidBodyHandle, detection_range: f32) -> Option<RigidBodyHandle> {
-        let rb = self.rigid_bodies.get(agent_body_handle).unwrap();
+    /// Nearest other dynamic body within `detection_range`.
+    pub fn get_closed_agent(&self, agent_body_handle: RigidBodyHandle, detection_range: f32) -> Result<Option<RigidBodyHandle>, PhysicsError> {
+        let rb = self.get_body(agent_body_handle)?;
         let pos1 = matrix_to_vec2(rb.position().translation);
         let mut dist = f32::INFINITY;
         let mut target: RigidBodyHandle = RigidBodyHandle::invalid();
@@ -337,9 +421,14 @@ impl Physics {
         };
         self.query_pipeline.intersections_with_shape(&self.rigid_bodies, &self.colliders, rb.position(), detector.shape(), filter,
             |collided| {
-                let rb2_handle = self.get_body_handle_from_collider(collided).unwrap();
-                let rb2 = self.rigid_bodies.get(rb2_handle).unwrap();
-                let pos2 = matrix_to_vec2(rb2.position().translation);
+                let rb2_handle = match self.get_body_handle_from_collider(collided) {
+                    Some(rb2_handle) => rb2_handle,
+                    None => return true,
+                };
+                let pos2 = match self.rigid_bodies.get(rb2_handle) {
+                    Some(rb2) => matrix_to_vec2(rb2.position().translation),
+                    None => return true,
+                };
                 let new_dist = pos1.distance(pos2);
                 if new_dist < dist {
                     dist = new_dist;
@@ -349,39 +438,82 @@ impl Physics {
             },
         );
         if dist < f32::INFINITY {
-            return Some(target);
+            return Ok(Some(target));
         } else {
-            return None;
+            return Ok(None);
         }
     }
 
-    fn update_grav(&mut self) {
-        self.grav_time += get_frame_time();
-        if self.grav_time >= 0.25 {
-            self.grav_time -= 0.25;
-            let mut gravity_map: HashMap<RigidBodyHandle, Vec2> = HashMap::new();
-            for (id1, body1) in self.rigid_bodies.iter() {
-                let mut gforce = Vec2::ZERO;
-                let pos1 = Vec2::new(body1.position().translation.x, body1.position().translation.y);
-                let coll1 = self.colliders.get(*body1.colliders().first().unwrap()).unwrap();
-                let size1 = coll1.shape().as_ball().unwrap().radius;
-                for (id2, body2) in self.rigid_bodies.iter() { 
-                    if id1 == id2 {
-                        continue;
-                    }
-                    let pos2 = Vec2::new(body2.position().translation.x, body2.position().translation.y);
-                    let dist = pos2.distance_squared(pos1);
-                    if dist >= 10000.0 { continue; }
-                    let coll2 = self.colliders.get(*body2.colliders().first().unwrap()).unwrap();
-                    let size2 = coll2.shape().as_ball().unwrap().radius;
-                    let dir = (pos1-pos2).normalize_or_zero();
-                    gforce += (GRAV*dir * (size1+size2)) / dist; 
+    /// Positions and masses (collider radius) of all dynamic ball bodies, in arena order.
+    fn get_gravity_sources(&self) -> (Vec<RigidBodyHandle>, Vec<Vec2>, Vec<f32>) {
+        let mut handles = vec![];
+        let mut positions = vec![];
+        let mut masses = vec![];
+        for (rbh, body) in self.rigid_bodies.iter().filter(|(_, body)| body.is_dynamic()) {
+            // only balls attract, other shapes have no defined size
+            let size = match self.get_object_size(rbh) {
+                Ok(size) => size,
+                Err(_) => continue,
+            };
+            handles.push(rbh);
+            positions.push(matrix_to_vec2(body.position().translation));
+            masses.push(size);
+        }
+        return (handles, positions, masses);
+    }
+
+    /// Exact O(n²) gravity: `GRAV * size1 * size2 * (pos1 - pos2) / (dist² + softening²)^(3/2)`.
+    /// With `period` set, the nearest periodic image of every body is used.
+    pub fn compute_grav(&self, period: Option<Vec2>) -> Vec<(RigidBodyHandle, Vec2)> {
+        let (handles, positions, masses) = self.get_gravity_sources();
+        let soft2 = GRAV_SOFTENING * GRAV_SOFTENING;
+        let mut forces = Vec::with_capacity(handles.len());
+        for i in 0..handles.len() {
+            let mut field = Vec2::ZERO;
+            for j in 0..handles.len() {
+                if i == j {
+                    continue;
+                }
+                let mut d = positions[i] - positions[j];
+                if let Some(size) = period {
+                    d = min_image(d, size);
                 }
-                gravity_map.insert(id1, gforce);
+                let r2 = d.length_squared() + soft2;
+                field += d * masses[j] / (r2 * r2.sqrt());
             }
-            for (rbh, gforce) in gravity_map.iter() {
-                let rb = self.rigid_bodies.get_mut(*rbh).unwrap();
-                rb.reset_forces(true);
+            forces.push((handles[i], GRAV * masses[i] * field));
+        }
+        return forces;
+    }
+
+    /// Same law as `compute_grav`, approximated with Barnes–Hut quadtree and opening angle `theta`.
+    pub fn compute_grav_barnes_hut(&self, theta: f32, period: Option<Vec2>) -> Vec<(RigidBodyHandle, Vec2)> {
+        let (handles, mut positions, masses) = self.get_gravity_sources();
+        if let Some(size) = period {
+            for pos in positions.iter_mut() {
+                *pos = wrap_around(pos, size);
+            }
+        }
+        let tree = QuadTree::new(&positions, &masses);
+        let mut forces = Vec::with_capacity(handles.len());
+        for i in 0..handles.len() {
+            let field = tree.get_field(positions[i], i, theta, GRAV_SOFTENING, period);
+            forces.push((handles[i], GRAV * masses[i] * field));
+        }
+        return forces;
+    }
+
+    /// Adds gravity forces to bodies; forces must be reset before.
+    pub fn update_grav(&mut self, mode: GravityMode, theta: f32, period: Option<Vec2>) {
+        let forces = match mode {
+            GravityMode::Off => {
+                return;
+            },
+            GravityMode::BruteForce => self.compute_grav(period),
+            GravityMode::BarnesHut => self.compute_grav_barnes_hut(theta, period),
+        };
+        for (rbh, gforce) in forces.iter() {
+            if let Some(rb) = self.rigid_bodies.get_mut(*rbh) {
                 rb.add_force(vector![gforce.x, gforce.y], true);
             }
         }
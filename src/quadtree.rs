@@ -0,0 +1,182 @@
+#![allow(unused)]
+
+use glam::Vec2;
+use crate::util::min_image;
+
+
+/// Depth limit, bodies at the same position end up together in one leaf.
+const MAX_DEPTH: usize = 32;
+
+struct Node {
+    center: Vec2,
+    half: f32,
+    mass: f32,
+    mass_pos: Vec2,
+    /// Index of the first of four children, 0 for leaves (root is never a child).
+    first_child: usize,
+    /// First body of a leaf, the others of a leaf at max depth follow through `QuadTree::next`.
+    body: Option<usize>,
+}
+
+impl Node {
+    fn new(center: Vec2, half: f32) -> Self {
+        Self { center, half, mass: 0.0, mass_pos: Vec2::ZERO, first_child: 0, body: None }
+    }
+
+    fn is_leaf(&self) -> bool {
+        return self.first_child == 0;
+    }
+
+    fn center_of_mass(&self) -> Vec2 {
+        return self.mass_pos / self.mass;
+    }
+}
+
+/// Barnes–Hut quadtree for long-range inverse-square forces.
+/// Cells seen from a body under angle smaller than `theta` (cell size / distance) are treated as
+/// a single mass in their center of mass, which makes a force evaluation O(n log n) instead of O(n²).
+/// With `theta = 0.5` the rms error of forces is usually below 2% of the rms force (see `tests/gravity.rs`).
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    positions: Vec<Vec2>,
+    masses: Vec<f32>,
+    /// Next body in the same leaf.
+    next: Vec<Option<usize>>,
+}
+
+impl QuadTree {
+
+    pub fn new(positions: &[Vec2], masses: &[f32]) -> Self {
+        let mut min = Vec2::splat(f32::INFINITY);
+        let mut max = Vec2::splat(f32::NEG_INFINITY);
+        for p in positions.iter() {
+            min = min.min(*p);
+            max = max.max(*p);
+        }
+        if positions.is_empty() {
+            min = Vec2::ZERO;
+            max = Vec2::ZERO;
+        }
+        let half = ((max - min).max_element() / 2.0).max(1.0) * 1.01;
+        let mut tree = Self {
+            nodes: vec![Node::new((min + max) / 2.0, half)],
+            positions: positions.to_vec(),
+            masses: masses.to_vec(),
+            next: vec![None; positions.len()],
+        };
+        for i in 0..positions.len() {
+            tree.insert(i);
+        }
+        return tree;
+    }
+
+    fn child_for(&self, node: usize, pos: Vec2) -> usize {
+        let n = &self.nodes[node];
+        let mut quadrant = 0;
+        if pos.x >= n.center.x {
+            quadrant += 1;
+        }
+        if pos.y >= n.center.y {
+            quadrant += 2;
+        }
+        return n.first_child + quadrant;
+    }
+
+    fn subdivide(&mut self, node: usize) {
+        let center = self.nodes[node].center;
+        let half = self.nodes[node].half / 2.0;
+        let first_child = self.nodes.len();
+        for offset in [Vec2::new(-half, -half), Vec2::new(half, -half), Vec2::new(-half, half), Vec2::new(half, half)] {
+            self.nodes.push(Node::new(center + offset, half));
+        }
+        self.nodes[node].first_child = first_child;
+    }
+
+    fn insert(&mut self, body: usize) {
+        let pos = self.positions[body];
+        let mass = self.masses[body];
+        let mut node = 0;
+        let mut depth = 0;
+        loop {
+            self.nodes[node].mass += mass;
+            self.nodes[node].mass_pos += pos * mass;
+            if self.nodes[node].is_leaf() {
+                let old = match self.nodes[node].body {
+                    None if self.nodes[node].mass == mass => {
+                        self.nodes[node].body = Some(body);
+                        return;
+                    },
+                    // leaf at max depth keeps all bodies inside in a list
+                    old if depth >= MAX_DEPTH => {
+                        self.next[body] = old;
+                        self.nodes[node].body = Some(body);
+                        return;
+                    },
+                    old => old,
+                };
+                self.nodes[node].body = None;
+                self.subdivide(node);
+                if let Some(old) = old {
+                    let child = self.child_for(node, self.positions[old]);
+                    self.nodes[child].mass = self.masses[old];
+                    self.nodes[child].mass_pos = self.positions[old] * self.masses[old];
+                    self.nodes[child].body = Some(old);
+                }
+            }
+            node = self.child_for(node, pos);
+            depth += 1;
+        }
+    }
+
+    /// Sum of `(pos - com) * mass / (dist² + softening²)^(3/2)` over the tree, without body `exclude`.
+    /// Multiply by the gravity constant and the body's own mass to get the force.
+    /// With `period` set, distances to cells use the nearest periodic image.
+    pub fn get_field(&self, pos: Vec2, exclude: usize, theta: f32, softening: f32, period: Option<Vec2>) -> Vec2 {
+        let mut field = Vec2::ZERO;
+        // depth first walk never holds more than 3 siblings per level plus 4 children
+        let mut stack = [0usize; 3 * MAX_DEPTH + 4];
+        let mut top = 1;
+        let soft2 = softening * softening;
+        while top > 0 {
+            top -= 1;
+            let node = &self.nodes[stack[top]];
+            if node.mass <= 0.0 {
+                continue;
+            }
+            if node.is_leaf() {
+                // bodies of a leaf are summed one by one, so only `exclude` itself is left out
+                let mut body = node.body;
+                while let Some(b) = body {
+                    if b != exclude {
+                        field += self.attraction(pos, self.positions[b], self.masses[b], soft2, period);
+                    }
+                    body = self.next[b];
+                }
+                continue;
+            }
+            let com = node.center_of_mass();
+            let d = match period {
+                Some(size) => min_image(pos - com, size),
+                None => pos - com,
+            };
+            if (2.0 * node.half) * (2.0 * node.half) < theta * theta * d.length_squared() {
+                field += self.attraction(pos, com, node.mass, soft2, period);
+            } else {
+                for c in 0..4 {
+                    stack[top] = node.first_child + c;
+                    top += 1;
+                }
+            }
+        }
+        return field;
+    }
+
+    fn attraction(&self, pos: Vec2, source: Vec2, mass: f32, soft2: f32, period: Option<Vec2>) -> Vec2 {
+        let d = match period {
+            Some(size) => min_image(pos - source, size),
+            None => pos - source,
+        };
+        let r2 = d.length_squared() + soft2;
+        return d * mass / (r2 * r2.sqrt());
+    }
+}
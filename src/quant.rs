@@ -5,8 +5,10 @@ use std::f32::consts::PI;
 use crate::util::*;
 use crate::physics::*;
 use crate::globals::*;
-use macroquad::{color, prelude::*};
-use macroquad::rand::*;
+use crate::element::*;
+use glam::Vec2;
+use crate::color::{self, *};
+use quad_rand::*;
 use rapier2d::geometry::*;
 use rapier2d::na::Vector2;
 use rapier2d::prelude::ImpulseJointHandle;
@@ -15,7 +17,11 @@ use rapier2d::prelude::{RigidBody, RigidBodyHandle};
 
 pub struct Quant {
     pub key: u64,
+    /// Index of quant species in `ElementsBox`.
+    pub element: usize,
     pub pos: Vec2,
+    /// Position before the last physics step, for interpolated drawing.
+    pub prev_pos: Vec2,
     pub rot: f32,
     pub mass: f32,
     pub vel: f32,
@@ -26,116 +32,80 @@ pub struct Quant {
     pub physics_handle: RigidBodyHandle,
     pub bounds_num: usize,
     pub bounds: Vec<ImpulseJointHandle>,
+    /// Liquid the quant was spawned in, index into `World::cohesion`.
+    pub cohesion: Option<usize>,
 }
 
 
 
 impl Quant {
     
-    pub fn new(size: f32, bounds_num: usize, color: Color, physics: &mut Physics) -> Self {
-        let settings = get_settings();
-        let key = gen_range(u64::MIN, u64::MAX);
-        //let size = rand::gen_range(settings.quant_size_min, settings.quant_size_max) as f32;
-        let pos = random_position(settings.world_w as f32, settings.world_h as f32);
+    pub fn new(element_id: usize, element: &Element, settings: &Settings, rng: &RandGenerator, physics: &mut Physics) -> Self {
+        let key = generate_key64(rng);
+        let size = element.radius;
+        let pos = random_position(rng, settings.world_w as f32, settings.world_h as f32);
         let shape = SharedShape::ball(size*0.75);
-        let rbh = physics.add_dynamic(key, &pos, 0.0, shape.clone(), PhysicsProperities::default());
-        //let color = random_color();
+        let rbh = physics.add_dynamic(key, &pos, 0.0, shape.clone(), element.get_physics_props());
         Self {
-            key: generate_key64(),
+            key,
+            element: element_id,
             pos,
-            rot: random_rotation(),
+            prev_pos: pos,
+            rot: random_rotation(rng),
             mass: 0.0,
             vel: 0.0,
             ang_vel: 0.0,
             size,
-            color,
+            color: element.get_color(),
             shape,
             physics_handle: rbh,
-            bounds_num,
+            bounds_num: element.valence,
             bounds: vec![],
+            cohesion: None,
         }
     }
 
-    pub fn new_custom(position: Vec2, size: f32, bounds_num: usize, color: Color, physics: &mut Physics) -> Self {
-        let settings = get_settings();
-        let key = gen_range(u64::MIN, u64::MAX);
+    pub fn new_custom(position: Vec2, element_id: usize, element: &Element, rng: &RandGenerator, physics: &mut Physics) -> Self {
+        let key = generate_key64(rng);
+        let size = element.radius;
         let shape = SharedShape::ball(size*0.9);
-        let rbh = physics.add_dynamic(key, &position, 0.0, shape.clone(), PhysicsProperities::default());
+        let rbh = physics.add_dynamic(key, &position, 0.0, shape.clone(), element.get_physics_props());
         Self {
-            key: generate_key64(),
+            key,
+            element: element_id,
             pos: position,
-            rot: random_rotation(),
+            prev_pos: position,
+            rot: random_rotation(rng),
             mass: 0.0,
             vel: 0.0,
             ang_vel: 0.0,
             size,
-            color,
+            color: element.get_color(),
             shape,
             physics_handle: rbh,
-            bounds_num,
+            bounds_num: element.valence,
             bounds: vec![],
+            cohesion: None,
         }
     }
 
 
-    pub fn draw(&self) {
-        let settings = get_settings();
-        let x0 = self.pos.x;
-        let y0 = self.pos.y;
-        draw_circle(x0, y0, self.size, self.color);
-    }    
+    pub fn get_draw_position(&self, alpha: f32, period: Option<Vec2>) -> Vec2 {
+        return lerp_position(self.prev_pos, self.pos, alpha, period);
+    }
 
     pub fn update(&mut self, physics: &mut Physics) {
         self.update_physics(physics);
     }
 
-    fn draw_circle(&self) {
-        let x0 = self.pos.x;
-        let y0 = self.pos.y;
-        draw_circle_lines(x0, y0, self.size, 4.0, self.color);
-    }
-
     fn update_physics(&mut self, physics: &mut Physics) {
-        let settings = get_settings();
-        let physics_data = physics.get_physics_data(self.physics_handle);
+        let physics_data = match physics.get_physics_data(self.physics_handle) {
+            Ok(physics_data) => physics_data,
+            Err(_) => return,
+        };
+        self.prev_pos = self.pos;
         self.pos = physics_data.position;
         self.rot = physics_data.rotation;
         self.mass = physics_data.mass;
-        //match physics.rigid_bodies.get_mut(self.physics_handle) {
-        //    Some(body) => {
-        //        //self.check_edges(body);
-        ////        let dir = Vec2::from_angle(self.rot);
-        ////        let v = dir * self.vel * settings.quant_speed;
-        ////        let rot = self.ang_vel * settings.quant_rotate;
-        ////        body.set_linvel(Vector2::new(v.x, v.y), true);
-        ////        body.set_angvel(rot, true);
-        //    }
-        //    None => {}
-        //}
-    }
-
-    fn check_edges(&mut self, body: &mut RigidBody) {
-        let settings = get_settings();
-        let mut raw_pos = matrix_to_vec2(body.position().translation);
-        let mut out_of_edge = false;
-        if raw_pos.x < -5.0 {
-            raw_pos.x = settings.world_w as f32;
-            out_of_edge = true;
-        } else if raw_pos.x > settings.world_w as f32 + 5.0 {
-            raw_pos.x = 0.0;
-            out_of_edge = true;
-        }
-        if raw_pos.y < -5.0 {
-            raw_pos.y = settings.world_h as f32;
-            out_of_edge = true;
-        } else if raw_pos.y > settings.world_h as f32 + 5.0 {
-            raw_pos.y = 0.0;
-            out_of_edge = true;
-        }
-        if out_of_edge {
-            body.set_position(make_isometry(raw_pos.x, raw_pos.y, self.rot), true);
-            //body.set_linvel([0.0, 0.0].into(), true);
-            //self.vel = 0.0;
-        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+#![allow(unused)]
+
+use std::fs;
+use std::io;
+use crate::world::{World, WorldStats};
+
+
+/// Stats of the world after given number of steps.
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    pub step: usize,
+    pub stats: WorldStats,
+}
+
+/// Steps the world without a window, taking a sample before the first step, every `every` steps and after the last one.
+/// `progress` is called after every step with the number of steps done.
+pub fn run(world: &mut World, steps: usize, every: usize, mut progress: impl FnMut(usize)) -> Vec<Sample> {
+    let every = every.max(1);
+    let mut samples = vec![Sample { step: 0, stats: world.get_stats() }];
+    for step in 1..=steps {
+        world.step();
+        if step.is_multiple_of(every) || step == steps {
+            samples.push(Sample { step, stats: world.get_stats() });
+        }
+        progress(step);
+    }
+    return samples;
+}
+
+/// Writes samples as CSV with one row per sample.
+pub fn save_metrics(path: &str, samples: &[Sample]) -> io::Result<()> {
+    let mut csv = format!("step,{}\n", WorldStats::csv_header());
+    for sample in samples.iter() {
+        csv.push_str(&format!("{},{}\n", sample.step, sample.stats.to_csv_row()));
+    }
+    fs::write(path, csv)?;
+    return Ok(());
+}
@@ -0,0 +1,117 @@
+#![allow(unused)]
+
+use std::fmt;
+use std::fs;
+use std::io;
+use serde::{Deserialize, Serialize};
+use crate::globals::*;
+use crate::interaction::InteractionMatrix;
+use crate::matter::MatterSpec;
+use crate::molecule::TemplateError;
+use crate::world::World;
+
+
+/// One generator run when a scenario is built, the same ones the QUANTS menu uses.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Spawn {
+    /// Quants of element with given symbol at random positions.
+    Element { element: String, count: usize },
+    /// Molecules from the library, by template name, at random places.
+    Molecule { template: String, count: usize },
+    /// Random star molecules, like the "Some" button.
+    Stars { count: usize },
+    /// Solid, liquid or gas filling a region.
+    Matter { spec: MatterSpec },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScenarioError {
+    UnknownElement(String),
+    UnknownTemplate(String),
+    Template(TemplateError),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::UnknownElement(symbol) => write!(f, "unknown element '{}'", symbol),
+            ScenarioError::UnknownTemplate(name) => write!(f, "no molecule template '{}' in library", name),
+            ScenarioError::Template(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Recipe of a starting world: settings, interactions and list of spawns, stored as JSON.
+/// Built with a given seed the scenario always gives the same world.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    pub settings: Settings,
+    /// Interaction matrix, resized to the element table; no interactions if not set.
+    pub interactions: Option<InteractionMatrix>,
+    /// Randomizes interactions with strengths up to this value, after `interactions` are set.
+    pub random_interactions: Option<f32>,
+    pub spawns: Vec<Spawn>,
+}
+
+impl Scenario {
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let scenario = serde_json::from_str(&json)?;
+        return Ok(scenario);
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        return Ok(());
+    }
+
+    /// Creates world with `settings` (scenario settings with overrides applied), loads assets and runs spawns in order.
+    pub fn build(&self, settings: Settings) -> Result<World, ScenarioError> {
+        let mut world = World::new(settings);
+        world.load_assets();
+        if let Some(interactions) = &self.interactions {
+            world.interactions = interactions.clone();
+            world.interactions.resize(world.elements.count());
+        }
+        if let Some(strength) = self.random_interactions {
+            world.interactions.randomize(&world.rng, strength);
+        }
+        for spawn in self.spawns.iter() {
+            match spawn {
+                Spawn::Element { element, count } => {
+                    let id = match world.elements.find(element) {
+                        Some(id) => id,
+                        None => return Err(ScenarioError::UnknownElement(element.clone())),
+                    };
+                    for _ in 0..*count {
+                        world.add_element(id);
+                    }
+                },
+                Spawn::Molecule { template, count } => {
+                    let id = match world.molecules.find(template) {
+                        Some(id) => id,
+                        None => return Err(ScenarioError::UnknownTemplate(template.clone())),
+                    };
+                    for _ in 0..*count {
+                        world.spawn_library_molecule(id).map_err(ScenarioError::Template)?;
+                    }
+                },
+                Spawn::Stars { count } => {
+                    world.add_some(*count);
+                },
+                Spawn::Matter { spec } => {
+                    if world.elements.get(spec.element).is_none() {
+                        return Err(ScenarioError::UnknownElement(spec.element.to_string()));
+                    }
+                    world.add_matter(spec);
+                },
+            }
+        }
+        world.update_molecules();
+        return Ok(world);
+    }
+}
@@ -1,109 +1,60 @@
 #![allow(unused)]
 
 use crate::camera::*;
-use crate::joint::MyJoint;
-use crate::quant::Quant;
-use crate::util::*;
-use crate::physics::*;
-use crate::collector::*;
-use crate::globals::*;
+use crate::draw::*;
+use crate::storage::*;
+use quantum::util::*;
+use quantum::globals::*;
+use quantum::world::*;
+use quantum::snapshot::Snapshot;
+use quantum::collector::{ElementsBox, MoleculesBox};
 use crate::ui::*;
+use quantum::timing::TimeControl;
 use macroquad::camera::Camera2D;
 use macroquad::prelude::*;
-use macroquad::experimental::collections::storage;
-use rapier2d::na::Point2;
-use rapier2d::na::Vector2;
-use rapier2d::prelude::ColliderSet;
-use rapier2d::prelude::FixedJointBuilder;
-use rapier2d::prelude::GenericJoint;
-use rapier2d::prelude::ImpulseJoint;
-use rapier2d::prelude::ImpulseJointHandle;
-use rapier2d::prelude::ImpulseJointSet;
-use rapier2d::prelude::JointAxis;
-use rapier2d::prelude::PrismaticJointBuilder;
 use rapier2d::prelude::RigidBodyHandle;
-use rapier2d::prelude::RigidBodySet;
-use rapier2d::prelude::UnitVector;
-use std::collections::HashMap;
-use std::f32::consts::PI;
 use std::process::exit;
 
 
 
 pub struct Simulation {
-    pub world_size: Vec2,
     pub font: Font,
-    pub physics: Physics,
+    pub world: World,
     pub camera: Camera2D,
-    pub running: bool,
-    pub sim_time: f64,
+    pub time: TimeControl,
     pub mouse_state: MouseState,
-    pub quants: QuantumCollector,
+    pub selected: Option<RigidBodyHandle>,
     ui: UI,
 }
 
 impl Simulation {
-    
+
     pub fn new(font: Font) -> Self {
         Self {
-            world_size: Vec2 {
-                x: f32::NAN,
-                y: f32::NAN,
-            },
             font,
-            physics: Physics::new(),
+            world: Self::create_world(),
             camera: create_camera(),
-            running: true,
-            sim_time: 0.0,
+            time: TimeControl::new(),
             mouse_state: MouseState { pos: Vec2::NAN },
-            quants: QuantumCollector::new(),
+            selected: None,
             ui: UI::new(),
         }
     }
 
-    fn reset_sim(&mut self, sim_name: Option<&str>) {
-        let settings = get_settings();
-        self.world_size = Vec2::new(settings.world_w as f32, settings.world_h as f32);
-        self.physics = Physics::new();
-        self.quants.quants.clear();
-        self.sim_time = 0.0;
-        self.mouse_state = MouseState { pos: Vec2::NAN };
-        self.running = true;
-        self.init();
-    }
-
-    pub fn init(&mut self) {
-        let settings = get_settings();
-        let quants_num = settings.quant_init_num;  
-        //for _ in 0..24 {
-        //    self.add_triplet(); 
-        //}  
-    }
-
-    pub fn autorun_new_sim(&mut self) {
-    }
-
-    fn update_quants(&mut self) {
-        for (_, quant) in self.quants.get_iter_mut() {
-            quant.update(&mut self.physics);
-        }
+    fn create_world() -> World {
+        let mut world = World::new(get_settings());
+        world.load_assets();
+        return world;
     }
 
     pub fn update(&mut self) {
-        self.ui.process();
+        self.ui.process(&mut self.world, &mut self.time, self.selected);
         self.process_signals();
         self.update_sim_state();
-        self.check_quants_num();
-        self.update_quants();
-        //self.update_motors();
-        self.physics.step_physics();
-    }
-
-    fn update_motors(&mut self) {
-        for (handle, joint) in self.physics.impulse_joint_set.iter_mut() {
-            if rand::gen_range(0, 100) == 100 {
-                joint.data.set_motor_velocity(JointAxis::AngX, random_unit()*10000.0, 0.2);
-            }
+        self.mouse_input();
+        let steps = self.time.steps_for_frame(get_frame_time(), self.world.physics.get_dt());
+        for _ in 0..steps {
+            self.world.step();
         }
     }
 
@@ -111,22 +62,20 @@ impl Simulation {
         //set_default_camera();
         set_camera(&self.camera);
         clear_background(BLACK);
-        draw_rectangle_lines(0.0, 0.0, self.world_size.x, self.world_size.y, 3.0, WHITE);
+        let world_size = self.world.world_size();
+        draw_rectangle_lines(0.0, 0.0, world_size.x, world_size.y, 3.0, WHITE);
         self.draw_grid(50);
         self.draw_joints();
         self.draw_quants();
+        self.draw_selection();
         self.ui.draw();
     }
 
     fn draw_joints(&self) {
-        for (handle, joint) in self.physics.impulse_joint_set.iter() {
-            let rbh1 = joint.body1;
-            let rbh2 = joint.body2;
-            let rb1 = self.physics.rigid_bodies.get(rbh1).unwrap() ;
-            let rb2 = self.physics.rigid_bodies.get(rbh2).unwrap() ;
-            let (p1, r1) = iso_to_vec2_rot(rb1.position());
-            let (p2, r2) = iso_to_vec2_rot(rb2.position());
-            draw_line(p1.x, p1.y, p2.x, p2.y, 2.0, BLUE);
+        let period = self.world.get_period();
+        let alpha = self.time.get_alpha(self.world.physics.get_dt());
+        for (handle, joint) in self.world.joints.get_iter() {
+            draw_joint(joint, alpha, period);
         }
     }
 
@@ -134,36 +83,127 @@ impl Simulation {
         let mut signals = mod_signals();
         if signals.add_single_quant {
             signals.add_single_quant = false;
-            self.add_triplet();
+            self.world.add_triplet();
         }
         if signals.add_some_quants {
             signals.add_some_quants = false;
-            for _ in 0..12 {
-                //self.add_triplet();
-                //self.add_hex();
-                self.add_custom(rand::gen_range(1, 6) as usize);
-            }
+            self.world.add_some(12);
         }
         if signals.add_hex_quant {
             signals.add_hex_quant = false;
-            self.add_hex();
+            self.world.add_hex();
+        }
+        if signals.add_state_matter {
+            signals.add_state_matter = false;
+            let spec = *self.ui.get_matter_spec();
+            self.world.add_matter(&spec);
         }
         if signals.reset_all {
             signals.reset_all = false;
-            self.reset();
+            // physics is rebuilt, so old handles would point at new bodies
+            self.selected = None;
+            self.world.reset();
+        }
+        if let Some(element_id) = signals.spawn_element {
+            signals.spawn_element = None;
+            self.world.add_element(element_id);
+        }
+        if let Some(id) = signals.spawn_molecule {
+            signals.spawn_molecule = None;
+            if let Err(e) = self.world.spawn_library_molecule(id) {
+                println!("[MOLECULES]: can't spawn: {}", e);
+            }
+        }
+        if signals.save_template {
+            signals.save_template = false;
+            self.save_template();
+        }
+        if signals.save_snapshot {
+            signals.save_snapshot = false;
+            self.save_snapshot();
+        }
+        if signals.load_snapshot {
+            signals.load_snapshot = false;
+            self.load_snapshot();
+        }
+    }
+
+    fn save_snapshot(&self) {
+        let path = self.ui.get_snapshot_path();
+        match Snapshot::from_world(&self.world).save(path) {
+            Ok(_) => println!("[SNAPSHOT]: saved to {}", path),
+            Err(e) => println!("[SNAPSHOT]: can't save {}: {}", path, e),
+        }
+    }
+
+    /// Saves molecule of the selected quant as template file and adds it to the library.
+    fn save_template(&mut self) {
+        let rbh = match self.selected {
+            Some(rbh) => rbh,
+            None => return,
+        };
+        let name = self.ui.get_template_name().to_string();
+        let template = self.world.template_from_molecule(rbh, &name);
+        let path = match template.validate(&self.world.elements).and_then(|_| template.file_path(MOLECULES_PATH)) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("[MOLECULES]: template {} is invalid: {}", name, e);
+                return;
+            },
+        };
+        match template.save(&path) {
+            Ok(_) => {
+                self.world.molecules.add(template);
+                println!("[MOLECULES]: saved to {}", path);
+            },
+            Err(e) => println!("[MOLECULES]: can't save {}: {}", path, e),
+        }
+    }
+
+    fn load_snapshot(&mut self) {
+        let path = self.ui.get_snapshot_path().to_string();
+        let world = match Snapshot::load(&path) {
+            Ok(snapshot) => snapshot.to_world().map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match world {
+            Ok(world) => {
+                let molecules = std::mem::take(&mut self.world.molecules);
+                self.world = world;
+                self.world.molecules = molecules;
+                self.selected = None;
+                println!("[SNAPSHOT]: loaded from {}", path);
+            },
+            Err(e) => println!("[SNAPSHOT]: can't load {}: {}", path, e),
         }
-        init_global_signals(signals);
     }
 
     fn draw_quants(&self) {
-        for (id, quant) in self.quants.get_iter() {
-            quant.draw();
+        let period = self.world.get_period();
+        let alpha = self.time.get_alpha(self.world.physics.get_dt());
+        for (id, quant) in self.world.quants.get_iter() {
+            draw_quant(quant, alpha, period);
+        }
+    }
+
+    fn draw_selection(&self) {
+        let rbh = match self.selected {
+            Some(rbh) => rbh,
+            None => return,
+        };
+        if let Some(quant) = self.world.get_quant(rbh) {
+            let pos = quant.get_draw_position(self.time.get_alpha(self.world.physics.get_dt()), self.world.get_period());
+            draw_circle_lines(pos.x, pos.y, quant.size + 3.0, 2.0, YELLOW);
+            if let Some((_, target)) = self.world.get_drag() {
+                draw_line(pos.x, pos.y, target.x, target.y, 1.0, YELLOW);
+            }
         }
     }
 
     fn draw_grid(&self, cell_size: u32) {
-        let w = self.world_size.x;
-        let h = self.world_size.y;
+        let world_size = self.world.world_size();
+        let w = world_size.x;
+        let h = world_size.y;
         let col_num = (w / cell_size as f32).floor() as u32;
         let row_num = (h / cell_size as f32).floor() as u32;
         for x in 0..col_num + 1 {
@@ -178,107 +218,57 @@ impl Simulation {
         if is_key_pressed(KeyCode::Escape) {
             exit(0);
         }
-    }
-
-    fn mouse_input(&mut self) {
-        if is_mouse_button_released(MouseButton::Left) {
-            let (mouse_posx, mouse_posy) = mouse_position();
-            let mouse_pos = Vec2::new(mouse_posx, mouse_posy);
-            let rel_coords = self.camera.screen_to_world(mouse_pos);
+        if self.ui.keyboard_over() {
+            return;
         }
-    }
-
-    fn update_sim_state(&mut self) {
-        let (mouse_x, mouse_y) = mouse_position();
-        self.mouse_state.pos = Vec2::new(mouse_x, mouse_y);
-    }
-
-    fn check_quants_num(&mut self) {
-        let settings = get_settings();
-        if self.quants.count() < settings.quant_min_num {
-            self.quants.add_many_quants(1, &mut self.physics);
+        if is_key_pressed(KeyCode::Space) {
+            self.time.toggle_pause();
         }
-    }
-
-    pub fn is_running(&self) -> bool {
-        return self.running;
-    }
-
-    fn add_triplet(&mut self) {
-        let mut vc = Vec2::new(400.0, 300.0);
-        vc = random_position(100.0, 100.0) + Vec2::new(WORLD_W/2.0-50.0, WORLD_H/2.0-50.0);
-        let q = Quant::new_custom(vc, 7.0, 3, BLUE, &mut self.physics);
-        let step = 2.0 * PI / 3.0;
-        let prev_quant: Option<RigidBodyHandle> = None;
-        //for m in 0..5 {
-            let major_quant = self.quants.add_quant(q);
-
-            for i in 0..3 {
-                let a = i as f32 * step;
-                let vd = Vec2::from_angle(a)*14.0;
-                let vq = vc + vd;
-                let q = Quant::new_custom(vq, 7.0, 3, GREEN, &mut self.physics);
-                let minor_quant = self.quants.add_quant(q);
-                let vr = (vq-vc)/2.0;
-                let bound = PrismaticJointBuilder::new(UnitVector::new_normalize(Vector2::new(vr.x, vr.y))).local_anchor1(Point2::new(vr.x, vr.y)).local_anchor2(Point2::new(-vr.x, -vr.y))
-                .limits([0.0, 1.0]).build();
-            let bound_handle = self.physics.impulse_joint_set.insert(major_quant, minor_quant, bound, true);
+        if is_key_pressed(KeyCode::Period) {
+            self.time.step();
+        }
+        if is_key_pressed(KeyCode::RightBracket) {
+            self.time.faster();
+        }
+        if is_key_pressed(KeyCode::LeftBracket) {
+            self.time.slower();
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.time.slow_motion = !self.time.slow_motion;
+        }
+        if is_key_pressed(KeyCode::Delete) {
+            if let Some(rbh) = self.selected.take() {
+                self.world.despawn_quant(rbh);
             }
-        //}
+        }
     }
 
-    fn add_hex(&mut self) {
-        let mut vc = Vec2::new(400.0, 300.0);
-        vc = random_position(100.0, 100.0) + Vec2::new(WORLD_W/2.0-50.0, WORLD_H/2.0-50.0);
-        let q = Quant::new_custom(vc, 7.0, 3, BLUE, &mut self.physics);
-        let step = 2.0 * PI / 6.0;
-        let prev_quant: Option<RigidBodyHandle> = None;
-        //for m in 0..5 {
-            let major_quant = self.quants.add_quant(q);
-
-            for i in 0..6 {
-                let a = i as f32 * step;
-                let vd = Vec2::from_angle(a)*14.0;
-                let vq = vc + vd;
-                let q = Quant::new_custom(vq, 7.0, 3, GREEN, &mut self.physics);
-                let minor_quant = self.quants.add_quant(q);
-                let vr = (vq-vc)/2.0;
-                let bound = PrismaticJointBuilder::new(UnitVector::new_normalize(Vector2::new(vr.x, vr.y))).local_anchor1(Point2::new(vr.x, vr.y)).local_anchor2(Point2::new(-vr.x, -vr.y))
-                .limits([0.0, 1.0]).build();
-            let bound_handle = self.physics.impulse_joint_set.insert(major_quant, minor_quant, bound, true);
+    /// Left click selects quant under the cursor, holding the button drags it with a spring.
+    fn mouse_input(&mut self) {
+        if self.selected.is_some_and(|rbh| self.world.get_quant(rbh).is_none()) {
+            self.selected = None;
+            self.world.clear_drag();
+        }
+        let (mouse_posx, mouse_posy) = mouse_position();
+        let mouse_pos = Vec2::new(mouse_posx, mouse_posy);
+        let rel_coords = self.camera.screen_to_world(mouse_pos);
+        if is_mouse_button_pressed(MouseButton::Left) && !self.ui.pointer_over() {
+            self.selected = self.world.pick_quant(rel_coords);
+            if let Some(rbh) = self.selected {
+                self.world.set_drag(rbh, rel_coords);
             }
-        //}
-    }
-
-    fn add_custom(&mut self, n: usize) {
-        let mut vc = Vec2::new(400.0, 300.0);
-        vc = random_position(100.0, 100.0) + Vec2::new(WORLD_W/2.0-50.0, WORLD_H/2.0-50.0);
-        let q = Quant::new_custom(vc, 9.0, 3, RED, &mut self.physics);
-        let step = 2.0 * PI / n as f32;
-        let prev_quant: Option<RigidBodyHandle> = None;
-        //for m in 0..5 {
-            let major_quant = self.quants.add_quant(q);
-
-            for i in 0..n {
-                let a = i as f32 * step;
-                let vd = Vec2::from_angle(a)*14.0;
-                let vq = vc + vd;
-                let q = Quant::new_custom(vq, 6.0, 3, GREEN, &mut self.physics);
-                let minor_quant = self.quants.add_quant(q);
-                let vr = (vq-vc)/2.0;
-                let bound = PrismaticJointBuilder::new(UnitVector::new_normalize(Vector2::new(vr.x, vr.y))).local_anchor1(Point2::new(vr.x, vr.y)).local_anchor2(Point2::new(-vr.x, -vr.y))
-                .limits([0.0, 1.0]).build();
-            let bound_handle = self.physics.impulse_joint_set.insert(major_quant, minor_quant, bound, true);
+        }
+        if let Some((rbh, _)) = self.world.get_drag() {
+            match is_mouse_button_down(MouseButton::Left) {
+                true => self.world.set_drag(rbh, rel_coords),
+                false => self.world.clear_drag(),
             }
-        //}
+        }
     }
 
-    fn reset(&mut self) {
-        self.physics.colliders = ColliderSet::new();
-        self.physics.impulse_joint_set = ImpulseJointSet::new();
-        self.physics.rigid_bodies = RigidBodySet::new();
-        self.quants = QuantumCollector::new();
+    fn update_sim_state(&mut self) {
+        let (mouse_x, mouse_y) = mouse_position();
+        self.mouse_state.pos = Vec2::new(mouse_x, mouse_y);
     }
 
 }
-
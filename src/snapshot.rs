@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use crate::globals::*;
+use crate::collector::ElementsBox;
+use crate::interaction::{ForceLaw, InteractionMatrix};
+use crate::quant::Quant;
+use crate::util::*;
+use crate::color::Color;
+use crate::world::World;
+use glam::Vec2;
+use rapier2d::na::{Isometry2, Vector2};
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+/// Snapshot that parsed but doesn't describe a consistent world.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// Quant with given key has an element id missing in the element table.
+    UnknownElement { key: u64, element: usize },
+    /// Joint refers to a quant key that isn't in the snapshot.
+    UnknownQuant(u64),
+    /// Quant with given key belongs to a liquid missing in `Snapshot::cohesion`.
+    UnknownLiquid { key: u64, liquid: usize },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::UnknownElement { key, element } => write!(f, "quant {} has unknown element {}", key, element),
+            SnapshotError::UnknownQuant(key) => write!(f, "joint refers to missing quant {}", key),
+            SnapshotError::UnknownLiquid { key, liquid } => write!(f, "quant {} belongs to missing liquid {}", key, liquid),
+        }
+    }
+}
+
+/// Complete, serializable state of the world: settings, quants and joints.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub sim_time: f64,
+    pub settings: Settings,
+    #[serde(default)]
+    pub elements: ElementsBox,
+    #[serde(default)]
+    pub interactions: InteractionMatrix,
+    /// Cohesion laws of liquids, see `World::cohesion`.
+    #[serde(default)]
+    pub cohesion: Vec<ForceLaw>,
+    pub quants: Vec<QuantSnapshot>,
+    pub joints: Vec<JointSnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QuantSnapshot {
+    pub key: u64,
+    #[serde(default)]
+    pub element: usize,
+    pub position: [f32; 2],
+    pub rotation: f32,
+    pub lin_vel: [f32; 2],
+    pub ang_vel: f32,
+    pub size: f32,
+    pub color: [f32; 4],
+    pub bounds_num: usize,
+    /// Indices into `Snapshot::joints`.
+    pub bounds: Vec<usize>,
+    /// Radius of the ball collider, which differs from `size` by spawn method and resizing;
+    /// missing in old snapshots, then the `Quant::new_custom` collider is kept.
+    #[serde(default)]
+    pub collider_radius: Option<f32>,
+    /// `LockedAxes` bits of the body, set for frozen quants.
+    #[serde(default)]
+    pub locked_axes: u8,
+    #[serde(default)]
+    pub cohesion: Option<usize>,
+}
+
+/// Impulse joint stored as the rapier `GenericJoint` description,
+/// with bodies referenced by quant keys.
+#[derive(Serialize, Deserialize)]
+pub struct JointSnapshot {
+    pub body1: u64,
+    pub body2: u64,
+    /// Local frames as `[x, y, angle]`.
+    pub frame1: [f32; 3],
+    pub frame2: [f32; 3],
+    pub locked_axes: u8,
+    pub limit_axes: u8,
+    pub limits: Vec<[f32; 2]>,
+    pub contacts_enabled: bool,
+    #[serde(default)]
+    pub strength: Option<f32>,
+    #[serde(default)]
+    pub max_extension: Option<f32>,
+    #[serde(default)]
+    pub rest_length: Option<f32>,
+}
+
+impl Snapshot {
+
+    pub fn from_world(world: &World) -> Self {
+        let physics = &world.physics;
+        let mut joints: Vec<JointSnapshot> = vec![];
+        let mut joint_index: HashMap<ImpulseJointHandle, usize> = HashMap::new();
+        for (handle, joint) in physics.impulse_joint_set.iter() {
+            let key1 = world.get_quant(joint.body1).map(|q| q.key);
+            let key2 = world.get_quant(joint.body2).map(|q| q.key);
+            if let (Some(body1), Some(body2)) = (key1, key2) {
+                let mut joint_snapshot = JointSnapshot::from_joint(body1, body2, &joint.data);
+                if let Some(bond) = world.joints.get(handle) {
+                    joint_snapshot.strength = Some(bond.strength);
+                    joint_snapshot.max_extension = Some(bond.max_extension);
+                    joint_snapshot.rest_length = Some(bond.rest_length);
+                }
+                joint_index.insert(handle, joints.len());
+                joints.push(joint_snapshot);
+            }
+        }
+        let mut quants: Vec<QuantSnapshot> = vec![];
+        // bodies are visited in rapier storage order, so a reloaded world keeps the same step order
+        for (handle, rb) in physics.rigid_bodies.iter() {
+            let quant = match world.get_quant(handle) {
+                Some(quant) => quant,
+                None => continue,
+            };
+            let lin_vel = [rb.linvel().x, rb.linvel().y];
+            let ang_vel = rb.angvel();
+            let position = (matrix_to_vec2(rb.position().translation), rb.rotation().angle());
+            let bounds = quant.bounds.iter().filter_map(|h| joint_index.get(h).copied()).collect();
+            let collider_radius = rb.colliders().first()
+                .and_then(|ch| physics.colliders.get(*ch))
+                .and_then(|collider| collider.shape().as_ball())
+                .map(|ball| ball.radius);
+            quants.push(QuantSnapshot {
+                key: quant.key,
+                element: quant.element,
+                position: [position.0.x, position.0.y],
+                rotation: position.1,
+                lin_vel,
+                ang_vel,
+                size: quant.size,
+                color: [quant.color.r, quant.color.g, quant.color.b, quant.color.a],
+                bounds_num: quant.bounds_num,
+                bounds,
+                collider_radius,
+                locked_axes: rb.locked_axes().bits(),
+                cohesion: quant.cohesion,
+            });
+        }
+        return Self {
+            sim_time: world.sim_time,
+            settings: world.settings,
+            elements: world.elements.clone(),
+            interactions: world.interactions.clone(),
+            cohesion: world.cohesion.clone(),
+            quants,
+            joints,
+        };
+    }
+
+    /// Rebuilds a fresh world from this snapshot. Fails instead of dropping quants or joints it can't rebuild.
+    pub fn to_world(&self) -> Result<World, SnapshotError> {
+        let mut world = World::new(self.settings);
+        world.sim_time = self.sim_time;
+        world.interactions = self.interactions.clone();
+        world.cohesion = self.cohesion.clone();
+        world.set_elements(self.elements.clone());
+        let mut bodies: HashMap<u64, RigidBodyHandle> = HashMap::new();
+        for q in self.quants.iter() {
+            let [r, g, b, a] = q.color;
+            let position = Vec2::new(q.position[0], q.position[1]);
+            let element = match world.elements.get(q.element) {
+                Some(element) => element,
+                None => return Err(SnapshotError::UnknownElement { key: q.key, element: q.element }),
+            };
+            if let Some(liquid) = q.cohesion.filter(|liquid| *liquid >= self.cohesion.len()) {
+                return Err(SnapshotError::UnknownLiquid { key: q.key, liquid });
+            }
+            let mut quant = Quant::new_custom(position, q.element, element, &world.rng, &mut world.physics);
+            quant.key = q.key;
+            quant.size = q.size;
+            quant.color = Color::new(r, g, b, a);
+            quant.bounds_num = q.bounds_num;
+            quant.rot = q.rotation;
+            quant.cohesion = q.cohesion;
+            if let Some(radius) = q.collider_radius {
+                quant.shape = SharedShape::ball(radius);
+            }
+            if let Some(rb) = world.physics.rigid_bodies.get_mut(quant.physics_handle) {
+                rb.set_position(make_isometry(position.x, position.y, q.rotation), true);
+                rb.set_linvel(Vector2::new(q.lin_vel[0], q.lin_vel[1]), true);
+                rb.set_angvel(q.ang_vel, true);
+                rb.set_locked_axes(LockedAxes::from_bits_truncate(q.locked_axes), true);
+                if let Some(collider) = rb.colliders().first().and_then(|ch| world.physics.colliders.get_mut(*ch)) {
+                    collider.set_shape(quant.shape.clone());
+                }
+            }
+            bodies.insert(q.key, world.quants.add_quant(quant));
+        }
+        // quant bounds lists are rebuilt by add_bond
+        for j in self.joints.iter() {
+            let rbh1 = *bodies.get(&j.body1).ok_or(SnapshotError::UnknownQuant(j.body1))?;
+            let rbh2 = *bodies.get(&j.body2).ok_or(SnapshotError::UnknownQuant(j.body2))?;
+            let handle = world.add_bond(rbh1, rbh2, j.to_joint());
+            if let Some(bond) = world.joints.get_mut(handle) {
+                bond.strength = j.strength.unwrap_or(bond.strength);
+                bond.max_extension = j.max_extension.unwrap_or(bond.max_extension);
+                bond.rest_length = j.rest_length.unwrap_or(bond.rest_length);
+            }
+        }
+        world.update_molecules();
+        return Ok(world);
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        return Ok(());
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let snapshot = serde_json::from_str(&json)?;
+        return Ok(snapshot);
+    }
+}
+
+impl JointSnapshot {
+
+    fn from_joint(body1: u64, body2: u64, joint: &GenericJoint) -> Self {
+        let f1 = joint.local_frame1;
+        let f2 = joint.local_frame2;
+        Self {
+            body1,
+            body2,
+            frame1: [f1.translation.x, f1.translation.y, f1.rotation.angle()],
+            frame2: [f2.translation.x, f2.translation.y, f2.rotation.angle()],
+            locked_axes: joint.locked_axes.bits(),
+            limit_axes: joint.limit_axes.bits(),
+            limits: joint.limits.iter().map(|l| [l.min, l.max]).collect(),
+            contacts_enabled: joint.contacts_enabled,
+            strength: None,
+            max_extension: None,
+            rest_length: None,
+        }
+    }
+
+    fn to_joint(&self) -> GenericJoint {
+        let mut joint = GenericJoint::new(JointAxesMask::from_bits_truncate(self.locked_axes));
+        joint.local_frame1 = Isometry2::new(Vector2::new(self.frame1[0], self.frame1[1]), self.frame1[2]);
+        joint.local_frame2 = Isometry2::new(Vector2::new(self.frame2[0], self.frame2[1]), self.frame2[2]);
+        joint.limit_axes = JointAxesMask::from_bits_truncate(self.limit_axes);
+        for (i, l) in self.limits.iter().enumerate().take(joint.limits.len()) {
+            joint.limits[i].min = l[0];
+            joint.limits[i].max = l[1];
+        }
+        joint.contacts_enabled = self.contacts_enabled;
+        return joint;
+    }
+}
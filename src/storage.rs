@@ -0,0 +1,27 @@
+use macroquad::experimental::collections::storage;
+use quantum::globals::*;
+use std::ops::DerefMut;
+
+
+pub fn init_global_settings(settings: Settings) {
+    storage::store(settings);
+}
+
+pub fn get_settings() -> Settings {
+    return *storage::get::<Settings>();
+}
+
+/// Mutable handle to the global settings, changes are visible after the handle is dropped.
+/// Don't call `init_global_settings` while holding it.
+pub fn mod_settings() -> impl DerefMut<Target = Settings> {
+    return storage::get_mut::<Settings>();
+}
+
+pub fn init_global_signals(signals: Signals) {
+    storage::store(signals);
+}
+
+/// Mutable handle to the global signals, see `mod_settings`.
+pub fn mod_signals() -> impl DerefMut<Target = Signals> {
+    return storage::get_mut::<Signals>();
+}
@@ -0,0 +1,231 @@
+#![allow(unused)]
+
+use std::fs;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::globals::*;
+use crate::runner;
+use crate::scenario::Scenario;
+use crate::world::WorldStats;
+
+
+/// Values a swept setting takes.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Values {
+    /// Numbers from `from` to `to` inclusive, every `step`.
+    Range { from: f64, to: f64, step: f64 },
+    /// Any JSON values, e.g. `["Walls", "Periodic"]`.
+    List { values: Vec<Value> },
+}
+
+/// One swept field of `Settings`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Param {
+    pub name: String,
+    #[serde(flatten)]
+    pub values: Values,
+}
+
+impl Param {
+
+    /// Values as JSON text, ready for `Settings::set_field`.
+    pub fn get_values(&self) -> Vec<String> {
+        match &self.values {
+            Values::Range { from, to, step } => {
+                if *step <= 0.0 || to < from {
+                    return vec![];
+                }
+                // counted, not accumulated, so float error doesn't add or drop the last value
+                let n = ((to - from) / step + 1e-9).floor() as usize;
+                return (0..=n).map(|i| {
+                    let value = from + i as f64 * step;
+                    return format!("{}", (value * 1e9).round() / 1e9);
+                }).collect();
+            },
+            Values::List { values } => {
+                return values.iter().map(|v| v.to_string()).collect();
+            },
+        }
+    }
+}
+
+/// Ensemble of headless runs: a scenario stepped with every combination of swept settings.
+/// Seeds are swept like any other setting, e.g. `{ "name": "seed", "from": 1, "to": 10, "step": 1 }`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sweep {
+    /// Scenario file; the default scenario if not set.
+    pub scenario: Option<String>,
+    pub steps: usize,
+    /// Worker threads; all cores if not set.
+    pub threads: Option<usize>,
+    pub params: Vec<Param>,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            scenario: None,
+            steps: 1000,
+            threads: None,
+            params: vec![],
+        }
+    }
+}
+
+/// Overrides of a single run, pairs of setting name and JSON value.
+pub type Combination = Vec<(String, String)>;
+
+/// Summary of one run: the world stats after the last step.
+pub struct RunResult {
+    pub index: usize,
+    pub params: Combination,
+    pub stats: Result<WorldStats, String>,
+    /// Wall time of the run in seconds.
+    pub elapsed: f64,
+}
+
+impl Sweep {
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let sweep = serde_json::from_str(&json)?;
+        return Ok(sweep);
+    }
+
+    /// Cartesian product of all params, the last param changes fastest.
+    pub fn combinations(&self) -> Vec<Combination> {
+        let mut combinations: Vec<Combination> = vec![vec![]];
+        for param in self.params.iter() {
+            let values = param.get_values();
+            let mut next = Vec::with_capacity(combinations.len() * values.len());
+            for combination in combinations.iter() {
+                for value in values.iter() {
+                    let mut c = combination.clone();
+                    c.push((param.name.clone(), value.clone()));
+                    next.push(c);
+                }
+            }
+            combinations = next;
+        }
+        return combinations;
+    }
+
+    /// Applies every combination to the scenario settings up front, so a typo fails before any run starts.
+    pub fn check(&self, scenario: &Scenario) -> Result<(), String> {
+        for param in self.params.iter() {
+            if param.get_values().is_empty() {
+                return Err(format!("param '{}' has no values", param.name));
+            }
+        }
+        for combination in self.combinations() {
+            apply(scenario.settings, &combination)?;
+        }
+        return Ok(());
+    }
+
+    pub fn get_threads(&self) -> usize {
+        match self.threads {
+            Some(threads) => return threads.max(1),
+            None => return thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        }
+    }
+
+    /// Runs all combinations on `get_threads()` workers, each building and stepping its own world.
+    /// `progress` is called on the calling thread after each finished run. Results are in combination order.
+    pub fn run(&self, scenario: &Scenario, mut progress: impl FnMut(&RunResult, usize)) -> Vec<RunResult> {
+        let combinations = self.combinations();
+        let next = AtomicUsize::new(0);
+        let (send, recv) = crossbeam::channel::unbounded::<RunResult>();
+        let mut results = Vec::with_capacity(combinations.len());
+        thread::scope(|scope| {
+            for _ in 0..self.get_threads().min(combinations.len()) {
+                let send = send.clone();
+                let (next, combinations) = (&next, &combinations);
+                scope.spawn(move || {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let params = match combinations.get(index) {
+                            Some(params) => params,
+                            None => return,
+                        };
+                        let result = run_single(scenario, index, params, self.steps);
+                        if send.send(result).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(send);
+            for result in recv.iter() {
+                progress(&result, combinations.len());
+                results.push(result);
+            }
+        });
+        results.sort_by_key(|r| r.index);
+        return results;
+    }
+}
+
+fn apply(mut settings: Settings, combination: &Combination) -> Result<Settings, String> {
+    for (name, value) in combination.iter() {
+        settings.set_field(name, value)?;
+    }
+    return Ok(settings);
+}
+
+fn run_single(scenario: &Scenario, index: usize, params: &Combination, steps: usize) -> RunResult {
+    let start = Instant::now();
+    let stats = apply(scenario.settings, params)
+        .and_then(|settings| scenario.build(settings).map_err(|e| e.to_string()))
+        .map(|mut world| {
+            world.verbose = false;
+            let samples = runner::run(&mut world, steps, steps, |_| {});
+            return samples.last().map(|s| s.stats).unwrap_or_else(|| world.get_stats());
+        });
+    return RunResult {
+        index,
+        params: params.clone(),
+        stats,
+        elapsed: start.elapsed().as_secs_f64(),
+    };
+}
+
+/// Quotes a CSV field if needed; JSON strings lose their quotes.
+fn csv_field(json: &str) -> String {
+    let text = match serde_json::from_str::<Value>(json) {
+        Ok(Value::String(s)) => s,
+        _ => json.to_string(),
+    };
+    if text.contains([',', '"', '\n']) {
+        return format!("\"{}\"", text.replace('"', "\"\""));
+    }
+    return text;
+}
+
+/// Writes one row per run: run index, swept values, final stats, wall time and error of failed runs.
+pub fn save_summary(path: &str, sweep: &Sweep, results: &[RunResult]) -> io::Result<()> {
+    let mut csv = String::from("run,");
+    for param in sweep.params.iter() {
+        csv.push_str(&format!("{},", param.name));
+    }
+    csv.push_str(&format!("{},elapsed,error\n", WorldStats::csv_header()));
+    let empty_stats = ",".repeat(WorldStats::csv_header().matches(',').count());
+    for result in results.iter() {
+        csv.push_str(&format!("{},", result.index));
+        for (_, value) in result.params.iter() {
+            csv.push_str(&format!("{},", csv_field(value)));
+        }
+        match &result.stats {
+            Ok(stats) => csv.push_str(&format!("{},{:.3},\n", stats.to_csv_row(), result.elapsed)),
+            Err(e) => csv.push_str(&format!("{},{:.3},{}\n", empty_stats, result.elapsed, csv_field(&Value::String(e.clone()).to_string()))),
+        }
+    }
+    fs::write(path, csv)?;
+    return Ok(());
+}
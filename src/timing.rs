@@ -0,0 +1,92 @@
+#![allow(unused)]
+
+use crate::globals::*;
+
+
+/// Decides how many fixed physics steps run in each rendered frame.
+/// Frame time scaled by speed goes into an accumulator and is spent in steps of `dt`,
+/// so simulation speed doesn't depend on frame rate. What is left in the accumulator
+/// tells how far between the last two physics states the frame should be drawn.
+/// Rendering and UI keep going while paused, only stepping stops.
+pub struct TimeControl {
+    pub running: bool,
+    /// Simulated seconds per real second.
+    pub speed: usize,
+    /// Divides speed by `SLOW_MOTION_FACTOR`.
+    pub slow_motion: bool,
+    /// Steps done by single `step` request.
+    pub step_size: usize,
+    pending_steps: usize,
+    accumulator: f32,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl TimeControl {
+
+    pub fn new() -> Self {
+        Self {
+            running: true,
+            speed: 1,
+            slow_motion: false,
+            step_size: 1,
+            pending_steps: 0,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.running = !self.running;
+        self.pending_steps = 0;
+        self.accumulator = 0.0;
+    }
+
+    /// Pauses and schedules `step_size` steps for the next frame.
+    pub fn step(&mut self) {
+        self.running = false;
+        self.pending_steps += self.step_size;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2).max(1);
+    }
+
+    pub fn get_time_scale(&self) -> f32 {
+        match self.slow_motion {
+            true => return self.speed as f32 / SLOW_MOTION_FACTOR,
+            false => return self.speed as f32,
+        }
+    }
+
+    /// Number of physics steps of length `dt` to run for a frame that took `frame_time` seconds.
+    pub fn steps_for_frame(&mut self, frame_time: f32, dt: f32) -> usize {
+        if !self.running {
+            self.accumulator = 0.0;
+            return std::mem::take(&mut self.pending_steps);
+        }
+        self.accumulator += frame_time * self.get_time_scale();
+        let steps = (self.accumulator / dt).floor() as usize;
+        if steps > MAX_STEPS_PER_FRAME {
+            self.accumulator = 0.0;
+            return MAX_STEPS_PER_FRAME;
+        }
+        self.accumulator -= steps as f32 * dt;
+        return steps;
+    }
+
+    /// Interpolation factor between previous and current physics state for drawing.
+    pub fn get_alpha(&self, dt: f32) -> f32 {
+        if !self.running {
+            return 1.0;
+        }
+        return (self.accumulator / dt).clamp(0.0, 1.0);
+    }
+}
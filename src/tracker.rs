@@ -0,0 +1,217 @@
+#![allow(unused)]
+
+use std::collections::HashMap;
+use crate::util::*;
+use crate::physics::Physics;
+use crate::collector::{QuantumCollector, ElementsBox};
+use crate::census::fingerprint;
+use glam::Vec2;
+use rapier2d::prelude::RigidBodyHandle;
+
+
+/// Connected component of the bond graph. Single unbonded quants are molecules too.
+#[derive(Clone)]
+pub struct Molecule {
+    pub id: u64,
+    /// Member quants in rapier storage order.
+    pub members: Vec<RigidBodyHandle>,
+    /// Number of quants of every element, indexed by element id.
+    pub composition: Vec<usize>,
+    /// Canonical hash of the bond graph, equal for all molecules of the same species.
+    pub fingerprint: u64,
+    pub center_of_mass: Vec2,
+    pub mass: f32,
+    pub momentum: Vec2,
+    /// Simulation time when the molecule got its id.
+    pub first_seen: f64,
+}
+
+impl Molecule {
+
+    /// Chemical formula like `BG3`, elements in table order.
+    pub fn formula(&self, elements: &ElementsBox) -> String {
+        let mut formula = String::new();
+        for (id, count) in self.composition.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let symbol = elements.get(id).map(|e| e.symbol.as_str()).unwrap_or("?");
+            formula.push_str(symbol);
+            if *count > 1 {
+                formula.push_str(&count.to_string());
+            }
+        }
+        return formula;
+    }
+
+    pub fn size(&self) -> usize {
+        return self.members.len();
+    }
+}
+
+/// Keeps molecule identities between steps. After every step components of the bond graph are recomputed
+/// and each one inherits the id of the previous molecule sharing most of its quants, so ids stay stable
+/// while molecules move, and the largest fragment keeps the id when a molecule splits or merges.
+#[derive(Default)]
+pub struct MoleculeTracker {
+    molecules: Vec<Molecule>,
+    by_quant: HashMap<RigidBodyHandle, usize>,
+    next_id: u64,
+}
+
+impl MoleculeTracker {
+
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn update(&mut self, quants: &QuantumCollector, physics: &Physics, elements_num: usize, sim_time: f64, period: Option<Vec2>) {
+        let handles: Vec<RigidBodyHandle> = physics.rigid_bodies.iter()
+            .filter(|(rbh, _)| quants.get(*rbh).is_some())
+            .map(|(rbh, _)| rbh).collect();
+        let index: HashMap<RigidBodyHandle, usize> = handles.iter().enumerate().map(|(i, rbh)| (*rbh, i)).collect();
+        let mut parent: Vec<usize> = (0..handles.len()).collect();
+        let mut edges: Vec<(usize, usize)> = vec![];
+        for (i, rbh) in handles.iter().enumerate() {
+            let quant = match quants.get(*rbh) {
+                Some(quant) => quant,
+                None => continue,
+            };
+            for joint_handle in quant.bounds.iter() {
+                if let Some(joint) = physics.impulse_joint_set.get(*joint_handle) {
+                    if let (Some(a), Some(b)) = (index.get(&joint.body1), index.get(&joint.body2)) {
+                        let (ra, rb) = (find_root(&mut parent, *a), find_root(&mut parent, *b));
+                        parent[ra.max(rb)] = ra.min(rb);
+                        // both ends list the joint, count it once
+                        if joint.body1 == *rbh {
+                            edges.push((*a, *b));
+                        }
+                    }
+                }
+            }
+        }
+        let mut groups: Vec<Vec<RigidBodyHandle>> = vec![];
+        let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+        // group and position in group of every quant
+        let mut place: Vec<(usize, usize)> = Vec::with_capacity(handles.len());
+        for (i, rbh) in handles.iter().enumerate() {
+            let root = find_root(&mut parent, i);
+            let g = *group_of_root.entry(root).or_insert_with(|| {
+                groups.push(vec![]);
+                groups.len() - 1
+            });
+            place.push((g, groups[g].len()));
+            groups[g].push(*rbh);
+        }
+        let mut group_edges: Vec<Vec<(usize, usize)>> = vec![vec![]; groups.len()];
+        for (a, b) in edges.iter() {
+            let (g, la) = place[*a];
+            group_edges[g].push((la, place[*b].1));
+        }
+
+        let old_molecules = std::mem::take(&mut self.molecules);
+        let mut claimed: Vec<bool> = vec![false; old_molecules.len()];
+        // bigger molecules choose first, so they keep their ids on split
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        order.sort_by_key(|g| std::cmp::Reverse(groups[*g].len()));
+        let mut inherited: Vec<Option<usize>> = vec![None; groups.len()];
+        for g in order {
+            let mut votes: HashMap<usize, usize> = HashMap::new();
+            for rbh in groups[g].iter() {
+                if let Some(old) = self.by_quant.get(rbh) {
+                    *votes.entry(*old).or_insert(0) += 1;
+                }
+            }
+            let best = votes.iter().filter(|(old, _)| !claimed[**old])
+                .max_by_key(|(old, count)| (**count, std::cmp::Reverse(old_molecules[**old].id)));
+            if let Some((old, _)) = best {
+                claimed[*old] = true;
+                inherited[g] = Some(*old);
+            }
+        }
+
+        self.by_quant.clear();
+        for (g, members) in groups.into_iter().enumerate() {
+            let (id, first_seen) = match inherited[g] {
+                Some(old) => (old_molecules[old].id, old_molecules[old].first_seen),
+                None => {
+                    self.next_id += 1;
+                    (self.next_id, sim_time)
+                },
+            };
+            let mut molecule = Molecule {
+                id,
+                members,
+                composition: vec![0; elements_num],
+                fingerprint: 0,
+                center_of_mass: Vec2::ZERO,
+                mass: 0.0,
+                momentum: Vec2::ZERO,
+                first_seen,
+            };
+            let origin = physics.get_object_position(molecule.members[0]).unwrap_or(Vec2::ZERO);
+            let mut weighted_pos = Vec2::ZERO;
+            let mut labels: Vec<usize> = Vec::with_capacity(molecule.members.len());
+            for rbh in molecule.members.iter() {
+                if let Some(quant) = quants.get(*rbh) {
+                    if quant.element >= molecule.composition.len() {
+                        molecule.composition.resize(quant.element + 1, 0);
+                    }
+                    molecule.composition[quant.element] += 1;
+                    labels.push(quant.element);
+                }
+                if let Some(rb) = physics.rigid_bodies.get(*rbh) {
+                    let mut pos = matrix_to_vec2(rb.position().translation);
+                    if let Some(size) = period {
+                        pos = origin + min_image(pos - origin, size);
+                    }
+                    weighted_pos += pos * rb.mass();
+                    molecule.mass += rb.mass();
+                    molecule.momentum += Vec2::new(rb.linvel().x, rb.linvel().y) * rb.mass();
+                }
+            }
+            if molecule.mass > 0.0 {
+                molecule.center_of_mass = weighted_pos / molecule.mass;
+            }
+            molecule.fingerprint = fingerprint(&labels, &group_edges[g]);
+            for rbh in molecule.members.iter() {
+                self.by_quant.insert(*rbh, self.molecules.len());
+            }
+            self.molecules.push(molecule);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.molecules.clear();
+        self.by_quant.clear();
+    }
+
+    /// Molecule containing the quant, as found in the last update.
+    pub fn get_molecule_of(&self, handle: RigidBodyHandle) -> Option<&Molecule> {
+        let i = self.by_quant.get(&handle)?;
+        return self.molecules.get(*i);
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Molecule> {
+        return self.molecules.iter().find(|m| m.id == id);
+    }
+
+    pub fn get_iter(&self) -> std::slice::Iter<'_, Molecule> {
+        return self.molecules.iter();
+    }
+
+    pub fn count(&self) -> usize {
+        return self.molecules.len();
+    }
+}
+
+fn find_root(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut i = i;
+    while parent[i] != root {
+        let next = parent[i];
+        parent[i] = root;
+        i = next;
+    }
+    return root;
+}
@@ -1,8 +1,34 @@
-use egui_macroquad::{*, egui::{Context, TopBottomPanel, RichText, Color32, menu}}; 
-use crate::globals::*;
+use egui_macroquad::{egui::{Context, TopBottomPanel, SidePanel, RichText, Color32, menu, Window, Grid, DragValue, ComboBox, Slider, ScrollArea, Ui}}; 
+use macroquad::prelude::Vec2;
+use quantum::util::Rect;
+use rapier2d::prelude::RigidBodyHandle;
+use quantum::globals::*;
+use crate::storage::*;
+use quantum::element::Element;
+use quantum::interaction::*;
+use quantum::world::World;
+use quantum::physics::GravityMode;
+use quantum::matter::*;
+use quantum::census::Species;
+use quantum::timing::TimeControl;
 
 pub struct UI {
     pointer_over: bool,
+    keyboard_over: bool,
+    snapshot_path: String,
+    show_interactions: bool,
+    selected_law: (usize, usize),
+    show_settings: bool,
+    /// Settings edited in SETTINGS window, applied to the world with Apply.
+    settings_draft: Settings,
+    preset_path: String,
+    show_matter: bool,
+    matter_spec: MatterSpec,
+    template_name: String,
+    show_census: bool,
+    census_path: String,
+    /// Hide species made of a single quant in CENSUS window.
+    census_hide_singles: bool,
 }
 
 
@@ -11,22 +37,73 @@ impl UI {
     pub fn new() -> Self {
         Self {
             pointer_over: false,
+            keyboard_over: false,
+            snapshot_path: String::from("snapshot.json"),
+            show_interactions: false,
+            selected_law: (0, 0),
+            show_settings: false,
+            settings_draft: get_settings(),
+            preset_path: String::from("preset.json"),
+            show_matter: false,
+            matter_spec: MatterSpec::default(),
+            template_name: String::from("molecule"),
+            show_census: false,
+            census_path: String::from("census.csv"),
+            census_hide_singles: true,
         }
     }
 
-    pub fn process(&mut self) {
+    pub fn process(&mut self, world: &mut World, time: &mut TimeControl, selected: Option<RigidBodyHandle>) {
         egui_macroquad::ui(|egui_ctx| {
             self.pointer_over = egui_ctx.is_pointer_over_area();
-            self.build_top_menu(egui_ctx);
+            self.keyboard_over = egui_ctx.wants_keyboard_input();
+            self.build_top_menu(egui_ctx, world, time);
+            if let Some(rbh) = selected {
+                self.build_inspector(egui_ctx, world, rbh);
+            }
+            if self.show_interactions {
+                self.build_interactions_window(egui_ctx, world);
+            }
+            if self.show_settings {
+                self.build_settings_window(egui_ctx, world);
+            }
+            if self.show_matter {
+                self.build_matter_window(egui_ctx, world);
+            }
+            if self.show_census {
+                self.build_census_window(egui_ctx, world);
+            }
         });
     }
 
 
+    /// True if the mouse is over any egui panel or window, so clicks shouldn't reach the world.
+    pub fn pointer_over(&self) -> bool {
+        return self.pointer_over;
+    }
+
+    /// True if an egui text field has focus, so key presses shouldn't control the simulation.
+    pub fn keyboard_over(&self) -> bool {
+        return self.keyboard_over;
+    }
+
+    pub fn get_template_name(&self) -> &str {
+        return &self.template_name;
+    }
+
+    pub fn get_matter_spec(&self) -> &MatterSpec {
+        return &self.matter_spec;
+    }
+
+    pub fn get_snapshot_path(&self) -> &str {
+        return &self.snapshot_path;
+    }
+
     pub fn draw(&self) {
         egui_macroquad::draw();
     }
 
-    fn build_top_menu(&mut self, egui_ctx: &Context) {
+    fn build_top_menu(&mut self, egui_ctx: &Context, world: &mut World, time: &mut TimeControl) {
         let mut signals = mod_signals();
         TopBottomPanel::top("top_panel").default_height(100.0).show(egui_ctx, |ui| {
             if !self.pointer_over {
@@ -40,29 +117,537 @@ impl UI {
                 menu::menu_button(ui, RichText::new("QUANTS").strong(), |ui| {
                     if ui.button(RichText::new("Single").strong().color(Color32::BLUE)).clicked() {
                         signals.add_single_quant = true;
-                        init_global_signals(signals);
                     }
                     if ui.button(RichText::new("Some").strong().color(Color32::RED)).clicked() {
                         signals.add_some_quants = true;
-                        init_global_signals(signals);
                     }
                     if ui.button(RichText::new("Hex").strong().color(Color32::RED)).clicked() {
                         signals.add_hex_quant = true;
-                        init_global_signals(signals);
                     }
-                    if ui.button(RichText::new("Solid").strong().color(Color32::GREEN)).clicked() {
-
+                    if ui.button(RichText::new("Matter").strong().color(Color32::GREEN)).clicked() {
+                        self.show_matter = true;
+                    }
+                    ui.separator();
+                    for (id, element) in world.elements.get_iter().enumerate() {
+                        let label = format!("{} {}", element.symbol, element.name);
+                        if ui.button(RichText::new(label).strong().color(element_color32(element))).clicked() {
+                            signals.spawn_element = Some(id);
+                        }
+                    }
+                    if world.molecules.count() > 0 {
+                        ui.separator();
+                    }
+                    for (id, template) in world.molecules.get_iter().enumerate() {
+                        if ui.button(RichText::new(&template.name).strong()).clicked() {
+                            signals.spawn_molecule = Some(id);
+                        }
                     }
                 });
 
                 menu::menu_button(ui, RichText::new("WORLD").strong(), |ui| {
                     if ui.button(RichText::new("Reset All").strong().color(Color32::RED)).clicked() {
                         signals.reset_all = true;
-                        init_global_signals(signals);
+                    }
+                    ui.separator();
+                    ui.text_edit_singleline(&mut self.snapshot_path);
+                    if ui.button(RichText::new("Save Snapshot").strong().color(Color32::GREEN)).clicked() {
+                        signals.save_snapshot = true;
+                    }
+                    if ui.button(RichText::new("Open Snapshot").strong().color(Color32::YELLOW)).clicked() {
+                        signals.load_snapshot = true;
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.show_interactions, RichText::new("Interactions").strong());
+                    ui.checkbox(&mut self.show_census, RichText::new("Census").strong());
+                    if ui.checkbox(&mut self.show_settings, RichText::new("Settings").strong()).changed() {
+                        self.settings_draft = world.settings;
+                    }
+                    ui.separator();
+                    ui.label(RichText::new("Boundary").strong());
+                    let before = (world.settings.boundary, world.settings.gravity_mode, world.settings.grav_theta);
+                    let mut boundary = world.settings.boundary;
+                    for mode in [BoundaryMode::Walls, BoundaryMode::Periodic, BoundaryMode::Open] {
+                        ui.radio_value(&mut boundary, mode, mode.name());
+                    }
+                    if boundary != world.settings.boundary {
+                        world.set_boundary(boundary);
+                    }
+                    ui.separator();
+                    ui.label(RichText::new("Gravity").strong());
+                    for mode in [GravityMode::Off, GravityMode::BruteForce, GravityMode::BarnesHut] {
+                        ui.radio_value(&mut world.settings.gravity_mode, mode, mode.name());
+                    }
+                    if world.settings.gravity_mode == GravityMode::BarnesHut {
+                        ui.add(Slider::new(&mut world.settings.grav_theta, 0.1..=1.5).text("theta"));
+                    }
+                    // changes made here go into the draft too, so a later Apply doesn't undo them
+                    let settings = world.settings;
+                    if (settings.boundary, settings.gravity_mode, settings.grav_theta) != before {
+                        for target in [&mut self.settings_draft, &mut *mod_settings()] {
+                            target.boundary = settings.boundary;
+                            target.gravity_mode = settings.gravity_mode;
+                            target.grav_theta = settings.grav_theta;
+                        }
                     }
                 });
+                ui.separator();
+                self.build_time_controls(ui, world, time);
             })
         });
     }
 
-}
\ No newline at end of file
+    /// Time controls in the top bar. Keys: Space pauses, `.` steps, `[` and `]` change speed, M toggles slow motion.
+    fn build_time_controls(&mut self, ui: &mut Ui, world: &World, time: &mut TimeControl) {
+        let (label, color) = match time.running {
+            true => ("PAUSE", Color32::YELLOW),
+            false => ("RUN", Color32::GREEN),
+        };
+        if ui.button(RichText::new(label).strong().color(color)).clicked() {
+            time.toggle_pause();
+        }
+        if ui.button(RichText::new("STEP").strong()).clicked() {
+            time.step();
+        }
+        ui.add(DragValue::new(&mut time.step_size).clamp_range(1..=1000).suffix(" steps"));
+        ui.separator();
+        if ui.button("<<").clicked() {
+            time.slower();
+        }
+        ui.label(RichText::new(format!("x{}", time.get_time_scale())).strong());
+        if ui.button(">>").clicked() {
+            time.faster();
+        }
+        ui.checkbox(&mut time.slow_motion, "slow");
+        ui.separator();
+        ui.label(format!("{:.2}s", world.sim_time));
+    }
+
+    fn build_inspector(&mut self, egui_ctx: &Context, world: &mut World, rbh: RigidBodyHandle) {
+        let quant = match world.get_quant(rbh) {
+            Some(quant) => quant,
+            None => return,
+        };
+        let key = quant.key;
+        let mut size = quant.size;
+        let mut color = [quant.color.r, quant.color.g, quant.color.b, quant.color.a];
+        let element = world.elements.get(quant.element).cloned();
+        let bounds_num = quant.bounds_num;
+        let data = match world.get_physics_data(rbh) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let (mut lin_vel, mut ang_vel) = world.get_velocity(rbh).unwrap_or((Vec2::ZERO, 0.0));
+        let mut frozen = world.is_frozen(rbh);
+        let bonded = world.get_bonded(rbh);
+        let molecule = world.tracker.get_molecule_of(rbh).cloned();
+        let mut vel_changed = false;
+        let mut size_changed = false;
+        let mut color_changed = false;
+        let mut frozen_changed = false;
+        let mut delete = false;
+        SidePanel::right("inspector").resizable(false).show(egui_ctx, |ui| {
+            if !self.pointer_over {
+                self.pointer_over = ui.ui_contains_pointer();
+            }
+            ui.heading("INSPECTOR");
+            Grid::new("inspector_grid").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label("key");
+                ui.label(format!("{:016X}", key));
+                ui.end_row();
+                ui.label("element");
+                match &element {
+                    Some(element) => ui.label(RichText::new(format!("{} ({})", element.name, element.symbol)).color(element_color32(element))),
+                    None => ui.label("-"),
+                };
+                ui.end_row();
+                ui.label("position");
+                ui.label(format!("[{:.1}, {:.1}]", data.position.x, data.position.y));
+                ui.end_row();
+                ui.label("rotation");
+                ui.label(format!("{:.2}", data.rotation));
+                ui.end_row();
+                ui.label("velocity");
+                ui.horizontal(|ui| {
+                    vel_changed |= ui.add(DragValue::new(&mut lin_vel.x).speed(1.0)).changed();
+                    vel_changed |= ui.add(DragValue::new(&mut lin_vel.y).speed(1.0)).changed();
+                });
+                ui.end_row();
+                ui.label("angular vel");
+                vel_changed |= ui.add(DragValue::new(&mut ang_vel).speed(0.1)).changed();
+                ui.end_row();
+                ui.label("mass");
+                ui.label(format!("{:.2}", data.mass));
+                ui.end_row();
+                ui.label("kinetic energy");
+                ui.label(format!("{:.1}", data.kin_eng.unwrap_or(0.0)));
+                ui.end_row();
+                ui.label("force");
+                match data.force {
+                    Some(force) => ui.label(format!("[{:.1}, {:.1}]", force.x, force.y)),
+                    None => ui.label("-"),
+                };
+                ui.end_row();
+                ui.label("size");
+                size_changed = ui.add(DragValue::new(&mut size).speed(0.1).clamp_range(1.0..=50.0)).changed();
+                ui.end_row();
+                ui.label("color");
+                color_changed = ui.color_edit_button_rgba_unmultiplied(&mut color).changed();
+                ui.end_row();
+                ui.label("frozen");
+                frozen_changed = ui.checkbox(&mut frozen, "").changed();
+                ui.end_row();
+                ui.label("bonds");
+                ui.label(format!("{}/{}", bonded.len(), bounds_num));
+                ui.end_row();
+            });
+            for other in bonded.iter() {
+                if let Some(quant) = world.get_quant(*other) {
+                    let symbol = world.elements.get(quant.element).map(|e| e.symbol.as_str()).unwrap_or("?");
+                    ui.label(format!("{} {:016X}", symbol, quant.key));
+                }
+            }
+            if let Some(molecule) = &molecule {
+                ui.separator();
+                ui.label(RichText::new(format!("Molecule #{}", molecule.id)).strong());
+                Grid::new("molecule_grid").num_columns(2).striped(true).show(ui, |ui| {
+                    ui.label("formula");
+                    ui.label(molecule.formula(&world.elements));
+                    ui.end_row();
+                    ui.label("species");
+                    match world.census.get(molecule.fingerprint) {
+                        Some(species) => ui.label(&species.name),
+                        None => ui.label("-"),
+                    };
+                    ui.end_row();
+                    ui.label("quants");
+                    ui.label(format!("{}", molecule.size()));
+                    ui.end_row();
+                    ui.label("mass");
+                    ui.label(format!("{:.2}", molecule.mass));
+                    ui.end_row();
+                    ui.label("center of mass");
+                    ui.label(format!("[{:.1}, {:.1}]", molecule.center_of_mass.x, molecule.center_of_mass.y));
+                    ui.end_row();
+                    ui.label("momentum");
+                    ui.label(format!("[{:.1}, {:.1}]", molecule.momentum.x, molecule.momentum.y));
+                    ui.end_row();
+                    ui.label("age");
+                    ui.label(format!("{:.2}s", world.sim_time - molecule.first_seen));
+                    ui.end_row();
+                });
+            }
+            ui.separator();
+            ui.label(RichText::new("Molecule template").strong());
+            ui.text_edit_singleline(&mut self.template_name);
+            if ui.button("Save Template").clicked() {
+                mod_signals().save_template = true;
+            }
+            ui.separator();
+            delete = ui.button(RichText::new("Delete Quant").strong().color(Color32::RED)).clicked();
+        });
+        if vel_changed {
+            world.set_velocity(rbh, lin_vel, ang_vel);
+        }
+        if size_changed {
+            world.set_quant_size(rbh, size);
+        }
+        if color_changed {
+            if let Some(quant) = world.quants.get_mut(rbh) {
+                let [r, g, b, a] = color;
+                quant.color = quantum::color::Color::new(r, g, b, a);
+            }
+        }
+        if frozen_changed {
+            world.set_frozen(rbh, frozen);
+        }
+        if delete {
+            world.despawn_quant(rbh);
+        }
+    }
+
+    fn build_settings_window(&mut self, egui_ctx: &Context, world: &mut World) {
+        let mut open = self.show_settings;
+        let draft = &mut self.settings_draft;
+        let mut apply = false;
+        let mut revert = false;
+        let mut save_preset = false;
+        let mut load_preset = false;
+        Window::new("SETTINGS").open(&mut open).resizable(false).show(egui_ctx, |ui| {
+            Grid::new("settings_grid").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label("seed");
+                ui.add(DragValue::new(&mut draft.seed));
+                ui.end_row();
+                ui.label("world size");
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut draft.world_w).clamp_range(100..=100000));
+                    ui.add(DragValue::new(&mut draft.world_h).clamp_range(100..=100000));
+                });
+                ui.end_row();
+                ui.label("quants min");
+                ui.add(DragValue::new(&mut draft.quant_min_num));
+                ui.end_row();
+                ui.label("bond strength");
+                ui.add(DragValue::new(&mut draft.bond_strength).speed(1000.0).clamp_range(0.0..=f32::INFINITY));
+                ui.end_row();
+                ui.label("bond max extension");
+                ui.add(DragValue::new(&mut draft.bond_max_extension).speed(0.5).clamp_range(0.0..=f32::INFINITY));
+                ui.end_row();
+                ui.label("bond activation");
+                ui.add(DragValue::new(&mut draft.bond_activation).speed(1.0).clamp_range(0.0..=f32::INFINITY));
+                ui.end_row();
+                ui.label("boundary");
+                ComboBox::from_id_source("boundary").selected_text(draft.boundary.name()).show_ui(ui, |ui| {
+                    for mode in [BoundaryMode::Walls, BoundaryMode::Periodic, BoundaryMode::Open] {
+                        ui.selectable_value(&mut draft.boundary, mode, mode.name());
+                    }
+                });
+                ui.end_row();
+                ui.label("long-range gravity");
+                ComboBox::from_id_source("gravity_mode").selected_text(draft.gravity_mode.name()).show_ui(ui, |ui| {
+                    for mode in [GravityMode::Off, GravityMode::BruteForce, GravityMode::BarnesHut] {
+                        ui.selectable_value(&mut draft.gravity_mode, mode, mode.name());
+                    }
+                });
+                ui.end_row();
+                ui.label("theta");
+                ui.add(DragValue::new(&mut draft.grav_theta).speed(0.01).clamp_range(0.0..=2.0));
+                ui.end_row();
+                ui.label("gravity vector");
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut draft.gravity[0]).speed(1.0));
+                    ui.add(DragValue::new(&mut draft.gravity[1]).speed(1.0));
+                });
+                ui.end_row();
+                ui.label("dt");
+                ui.add(DragValue::new(&mut draft.dt).speed(0.0005).clamp_range(0.0001..=0.1));
+                ui.end_row();
+                ui.label("solver iterations");
+                ui.add(DragValue::new(&mut draft.solver_iterations).clamp_range(1..=100));
+                ui.end_row();
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                apply = ui.button(RichText::new("Apply").strong().color(Color32::GREEN)).clicked();
+                revert = ui.button(RichText::new("Revert").strong().color(Color32::RED)).clicked();
+            });
+            ui.separator();
+            ui.text_edit_singleline(&mut self.preset_path);
+            ui.horizontal(|ui| {
+                save_preset = ui.button("Save Preset").clicked();
+                load_preset = ui.button("Load Preset").clicked();
+            });
+        });
+        if apply {
+            *mod_settings() = self.settings_draft;
+            world.apply_settings(self.settings_draft);
+        }
+        if revert {
+            self.settings_draft = world.settings;
+        }
+        if save_preset {
+            match self.settings_draft.save(&self.preset_path) {
+                Ok(_) => println!("[SETTINGS]: preset saved to {}", self.preset_path),
+                Err(e) => println!("[SETTINGS]: can't save {}: {}", self.preset_path, e),
+            }
+        }
+        if load_preset {
+            match Settings::load(&self.preset_path) {
+                Ok(settings) => {
+                    self.settings_draft = settings;
+                    println!("[SETTINGS]: preset loaded from {}", self.preset_path);
+                },
+                Err(e) => println!("[SETTINGS]: can't load {}: {}", self.preset_path, e),
+            }
+        }
+        self.show_settings = open;
+    }
+
+    fn build_matter_window(&mut self, egui_ctx: &Context, world: &World) {
+        let mut open = self.show_matter;
+        let spec = &mut self.matter_spec;
+        let mut generate = false;
+        Window::new("MATTER").open(&mut open).resizable(false).show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                for state in [MatterState::Solid, MatterState::Liquid, MatterState::Gas] {
+                    ui.radio_value(&mut spec.state, state, state.name());
+                }
+            });
+            Grid::new("matter_grid").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label("element");
+                let selected = world.elements.get(spec.element).map(|e| e.name.clone()).unwrap_or_default();
+                ComboBox::from_id_source("matter_element").selected_text(selected).show_ui(ui, |ui| {
+                    for (id, element) in world.elements.get_iter().enumerate() {
+                        ui.selectable_value(&mut spec.element, id, RichText::new(&element.name).color(element_color32(element)));
+                    }
+                });
+                ui.end_row();
+                ui.label("count");
+                ui.add(DragValue::new(&mut spec.count).clamp_range(1..=100000));
+                ui.end_row();
+                ui.label("region x / y");
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut spec.region.x));
+                    ui.add(DragValue::new(&mut spec.region.y));
+                });
+                ui.end_row();
+                ui.label("region w / h");
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut spec.region.w).clamp_range(1.0..=f32::INFINITY));
+                    ui.add(DragValue::new(&mut spec.region.h).clamp_range(1.0..=f32::INFINITY));
+                });
+                ui.end_row();
+                match spec.state {
+                    MatterState::Solid => {
+                        ui.label("lattice");
+                        ui.horizontal(|ui| {
+                            for lattice in [Lattice::Square, Lattice::Hexagonal] {
+                                ui.radio_value(&mut spec.lattice, lattice, lattice.name());
+                            }
+                        });
+                        ui.end_row();
+                    },
+                    MatterState::Liquid => {
+                        ui.label("cohesion");
+                        ui.add(DragValue::new(&mut spec.cohesion).speed(100.0));
+                        ui.end_row();
+                        ui.label("temperature");
+                        ui.add(DragValue::new(&mut spec.temperature).speed(1.0).clamp_range(0.0..=f32::INFINITY));
+                        ui.end_row();
+                    },
+                    MatterState::Gas => {
+                        ui.label("temperature");
+                        ui.add(DragValue::new(&mut spec.temperature).speed(1.0).clamp_range(0.0..=f32::INFINITY));
+                        ui.end_row();
+                    },
+                }
+            });
+            if let Some(element) = world.elements.get(spec.element) {
+                let capacity = spec.get_capacity(element.radius);
+                if spec.count > capacity {
+                    ui.label(RichText::new(format!("region holds only {} quants", capacity)).color(Color32::YELLOW));
+                }
+                if spec.get_missing_bonds(element.valence).is_some() {
+                    ui.label(RichText::new(format!("{} bonds only {} of {} lattice neighbors", element.symbol, element.valence, spec.lattice.coordination()))
+                        .color(Color32::YELLOW));
+                }
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Whole World").clicked() {
+                    let size = world.world_size();
+                    spec.region = Rect::new(0.0, 0.0, size.x, size.y);
+                }
+                generate = ui.button(RichText::new("Generate").strong().color(Color32::GREEN)).clicked();
+            });
+        });
+        if generate {
+            mod_signals().add_state_matter = true;
+        }
+        self.show_matter = open;
+    }
+
+    fn build_census_window(&mut self, egui_ctx: &Context, world: &World) {
+        let mut open = self.show_census;
+        let mut species: Vec<&Species> = world.census.get_iter()
+            .filter(|s| !self.census_hide_singles || s.size > 1).collect();
+        species.sort_by(|a, b| b.count.cmp(&a.count).then(b.peak.cmp(&a.peak)));
+        let mut export = false;
+        Window::new("CENSUS").open(&mut open).resizable(false).show(egui_ctx, |ui| {
+            ui.label(format!("species: {}  molecules: {}", world.census.count(), world.tracker.count()));
+            ui.checkbox(&mut self.census_hide_singles, "hide single quants");
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                Grid::new("census_grid").num_columns(6).striped(true).show(ui, |ui| {
+                    for header in ["name", "formula", "count", "peak", "first seen", "last seen"] {
+                        ui.label(RichText::new(header).strong());
+                    }
+                    ui.end_row();
+                    for s in species.iter() {
+                        ui.label(&s.name).on_hover_text(format!("{:016X}", s.fingerprint));
+                        ui.label(&s.formula);
+                        ui.label(format!("{}", s.count));
+                        ui.label(format!("{}", s.peak));
+                        ui.label(format!("{:.2}s", s.first_seen));
+                        ui.label(format!("{:.2}s", s.last_seen));
+                        ui.end_row();
+                    }
+                });
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.census_path);
+                export = ui.button("Export CSV").clicked();
+            });
+        });
+        if export {
+            match world.census.export(&self.census_path) {
+                Ok(_) => println!("[CENSUS]: exported to {}", self.census_path),
+                Err(e) => println!("[CENSUS]: can't export {}: {}", self.census_path, e),
+            }
+        }
+        self.show_census = open;
+    }
+
+    fn build_interactions_window(&mut self, egui_ctx: &Context, world: &mut World) {
+        let mut open = self.show_interactions;
+        Window::new("INTERACTIONS").open(&mut open).resizable(false).show(egui_ctx, |ui| {
+            ui.label("force on row species from column species (+ attract / - repel)");
+            Grid::new("interactions_grid").striped(true).show(ui, |ui| {
+                ui.label("");
+                for element in world.elements.get_iter() {
+                    ui.label(RichText::new(&element.symbol).strong().color(element_color32(element)));
+                }
+                ui.end_row();
+                for (i, element) in world.elements.get_iter().enumerate() {
+                    if ui.selectable_label(self.selected_law.0 == i, RichText::new(&element.symbol).strong().color(element_color32(element))).clicked() {
+                        self.selected_law.0 = i;
+                    }
+                    for j in 0..world.elements.count() {
+                        if let Some(law) = world.interactions.get_mut(i, j) {
+                            let response = ui.add(DragValue::new(&mut law.strength).speed(100.0));
+                            if response.clicked() || response.dragged() {
+                                self.selected_law = (i, j);
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            let (i, j) = self.selected_law;
+            let names = match (world.elements.get(i), world.elements.get(j)) {
+                (Some(e1), Some(e2)) => format!("{} <- {}", e1.symbol, e2.symbol),
+                _ => String::new(),
+            };
+            if let Some(law) = world.interactions.get_mut(i, j) {
+                ui.label(RichText::new(names).strong());
+                ui.horizontal(|ui| {
+                    ui.label("strength");
+                    ui.add(DragValue::new(&mut law.strength).speed(100.0));
+                    ui.label("range");
+                    ui.add(DragValue::new(&mut law.range).speed(1.0).clamp_range(0.0..=1000.0));
+                    ComboBox::from_id_source("falloff").selected_text(law.falloff.name()).show_ui(ui, |ui| {
+                        for falloff in [Falloff::Constant, Falloff::Linear, Falloff::Quadratic] {
+                            ui.selectable_value(&mut law.falloff, falloff, falloff.name());
+                        }
+                    });
+                });
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Randomize").clicked() {
+                    world.interactions.randomize(&world.rng, 20000.0);
+                }
+                if ui.button("Clear").clicked() {
+                    world.interactions.clear();
+                }
+            });
+        });
+        self.show_interactions = open;
+    }
+
+}
+
+fn element_color32(element: &Element) -> Color32 {
+    let [r, g, b, _] = element.color;
+    return Color32::from_rgb((r*255.0) as u8, (g*255.0) as u8, (b*255.0) as u8);
+}
@@ -1,39 +1,48 @@
 #![allow(unused)]
 
 use std::f32::consts::PI;
-use macroquad::{color, prelude::*};
-use macroquad::rand::*;
+use glam::Vec2;
+use crate::color::{self, *};
+use quad_rand::*;
 use rapier2d::prelude::*;
 use rapier2d::parry::query::contact; 
 use rapier2d::na::{Isometry2, Vector2, Translation, Point2, Const};
 use crate::globals::*;
+use serde::{Deserialize, Serialize};
 
-pub fn generate_key64() -> u64 {
-    return gen_range(u64::MIN, u64::MAX);
+pub fn generate_key64(rng: &RandGenerator) -> u64 {
+    return rng.gen_range(u64::MIN, u64::MAX);
 }
 
-pub fn random_unit() -> f32 {
-    return rand::gen_range(-1.0, 1.0);
+pub fn random_unit(rng: &RandGenerator) -> f32 {
+    return rng.gen_range(-1.0, 1.0);
 }
 
-pub fn random_position(x_max: f32, y_max: f32) -> Vec2 {
-    let x = rand::gen_range(0.0, x_max);
-    let y = rand::gen_range(0.0, y_max);
+pub fn random_position(rng: &RandGenerator, x_max: f32, y_max: f32) -> Vec2 {
+    let x = rng.gen_range(0.0, x_max);
+    let y = rng.gen_range(0.0, y_max);
     return Vec2::new(x, y);
 }
 
-pub fn random_rotation() -> f32 {
-    let rot = rand::gen_range(0.0, PI * 2.0);
+pub fn random_rotation(rng: &RandGenerator) -> f32 {
+    let rot = rng.gen_range(0.0, PI * 2.0);
     return rot;
 }
 
-pub fn random_unit_vec2() -> Vec2 {
-    let x = rand::gen_range(-1.0, 1.0);
-    let y = rand::gen_range(-1.0, 1.0);
+/// Standard normal distributed number (Box–Muller).
+pub fn random_normal(rng: &RandGenerator) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON, 1.0);
+    let u2: f32 = rng.gen_range(0.0, 1.0);
+    return (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+}
+
+pub fn random_unit_vec2(rng: &RandGenerator) -> Vec2 {
+    let x = rng.gen_range(-1.0, 1.0);
+    let y = rng.gen_range(-1.0, 1.0);
     return Vec2::new(x, y).normalize_or_zero();
 }
 
-pub fn random_color() -> color::Color {
+pub fn random_color(rng: &RandGenerator) -> color::Color {
     let colors = vec![
         LIGHTGRAY, GRAY, DARKGRAY, YELLOW, GOLD, ORANGE, PINK, RED, 
         MAROON, GREEN, LIME, DARKGREEN, SKYBLUE, BLUE, DARKBLUE, PURPLE, 
@@ -41,13 +50,13 @@ pub fn random_color() -> color::Color {
     ];
     //let colors = vec![RED, GREEN, BLUE, YELLOW, ORANGE, GRAY, SKYBLUE, LIME, ];
     let num = colors.len();
-    let c = rand::gen_range(0, num);
+    let c = rng.gen_range(0, num);
     return colors[c];
 }
 
-pub fn random_color5() -> color::Color {
+pub fn random_color5(rng: &RandGenerator) -> color::Color {
     let colors = [RED, BLUE, GREEN, YELLOW, WHITE];
-    let c = rand::gen_range(0, 5);
+    let c = rng.gen_range(0, 5);
     return colors[c];
 }
 
@@ -57,20 +66,14 @@ pub fn angle2vec2(angle: f32) -> Vec2 {
     return v;
 }
 
-pub fn wrap_around(v: &Vec2) -> Vec2 {
-    let tolerance = 5.0;
-    let mut vr = Vec2::new(v.x, v.y);
-    if vr.x > WORLD_W + tolerance {
-        vr.x = 0.0 - tolerance;
-    } else if vr.x < 0.0 - tolerance {
-        vr.x = WORLD_W + tolerance;
-    }
-    if vr.y > WORLD_H + tolerance {
-        vr.y = 0.0 - tolerance;
-    } else if vr.y < 0.0 - tolerance {
-        vr.y = WORLD_H + tolerance;
-    }
-    return vr;
+/// Moves position into `[0, size)` rectangle of periodic world.
+pub fn wrap_around(v: &Vec2, size: Vec2) -> Vec2 {
+    return Vec2::new(v.x.rem_euclid(size.x), v.y.rem_euclid(size.y));
+}
+
+/// Shortest periodic image of offset `d` in world of given size.
+pub fn min_image(d: Vec2, size: Vec2) -> Vec2 {
+    return d - size * (d / size).round();
 }
 
 pub fn iso_to_vec2_rot(isometry: &Isometry<Real>) -> (Vec2, f32) {
@@ -88,7 +91,7 @@ pub fn matrix_to_vec2(translation: Translation<f32, 2>) -> Vec2 {
     return Vec2::new(translation.x, translation.y);
 }
 
-pub fn map_polygon(n: usize, r: f32, dev: f32) -> Vec<Vec2> {
+pub fn map_polygon(rng: &RandGenerator, n: usize, r: f32, dev: f32) -> Vec<Vec2> {
     let mut points: Vec<Vec2> = vec![];
     let s = 2.0 * PI / (n as f32);
     let mut a = 2.0 * PI;
@@ -96,7 +99,7 @@ pub fn map_polygon(n: usize, r: f32, dev: f32) -> Vec<Vec2> {
         a = s * i as f32;
         let x = a.sin();
         let y = a.cos();
-        let deviation = rand::gen_range(-dev, dev);
+        let deviation = rng.gen_range(-dev, dev);
         let radius = r + r * deviation;
         let v = Vec2::new(x, y)*radius;
         points.push(v);
@@ -108,7 +111,7 @@ fn vec2_to_point2(v: &Vec2) -> Point2<f32> {
     return Point2::new(v.x, v.y);
 }
 
-pub fn vec2_to_point2_collection(vec2_list: &Vec<Vec2>) -> Vec<Point2<f32>> {
+pub fn vec2_to_point2_collection(vec2_list: &[Vec2]) -> Vec<Point2<f32>> {
     let mut points: Vec<Point2<f32>> = vec![];
     for v in vec2_list.iter() {
         let p = Point2::new(v.x, v.y);
@@ -117,7 +120,7 @@ pub fn vec2_to_point2_collection(vec2_list: &Vec<Vec2>) -> Vec<Point2<f32>> {
     return points;
 }
 
-pub fn vec2_to_point2_array(vec2_list: &Vec<Vec2>) -> Matrix<Point2<f32>> {
+pub fn vec2_to_point2_array(vec2_list: &[Vec2]) -> Matrix<Point2<f32>> {
     let l = vec2_list.len();
     let mut points: Matrix<Point2<f32>>;
     let vecs = vec2_to_point2_collection(vec2_list);
@@ -132,18 +135,15 @@ pub fn contact_mouse(mouse_pos: Vec2, target_pos: Vec2, target_rad: f32) -> bool
     let pos2 = make_isometry(v2.x, v2.y, 0.0);
     let ball1 = Ball::new(2.0);
     let ball2 = Ball::new(target_rad);
-    match contact(&pos1, &ball1, &pos2, &ball2, 0.0).unwrap() {
-        Some(_) => true,
-        None => false,
-    }
+    return contact(&pos1, &ball1, &pos2, &ball2, 0.0).unwrap().is_some();
 }
 
-pub fn make_regular_poly(n: usize, r: f32, dev: Option<f32>) -> Vec<Vec2> {
+pub fn make_regular_poly(rng: &RandGenerator, n: usize, r: f32, dev: Option<f32>) -> Vec<Vec2> {
     let s = 2.0*PI/n as f32;
     let mut verts: Vec<Vec2> = vec![];
     for i in 0..n {
         let d = match dev {
-            Some(deviation) => rand::gen_range(-deviation, deviation),
+            Some(deviation) => rng.gen_range(-deviation, deviation),
             None => 0.0,
         };
 
@@ -175,7 +175,39 @@ pub fn make_regular_poly_indices(n: usize, r: f32) -> (Vec<Vec2>, Vec<[u32; DIM]
     return (verts, indices);
 }
 
-pub fn create_name(num: usize) -> String {
+/// Axis-aligned rectangle with top-left corner at `x`, `y`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        return point.x >= self.x && point.x < self.x + self.w && point.y >= self.y && point.y < self.y + self.h;
+    }
+}
+
+/// Position between two consecutive physics states, `alpha` 0 is `prev` and 1 is `pos`.
+/// In periodic world it moves along the shortest way, so wrapped bodies don't cross the whole screen.
+pub fn lerp_position(prev: Vec2, pos: Vec2, alpha: f32, period: Option<Vec2>) -> Vec2 {
+    match period {
+        Some(size) => {
+            return wrap_around(&(prev + min_image(pos - prev, size) * alpha), size);
+        },
+        None => {
+            return prev.lerp(pos, alpha);
+        },
+    }
+}
+
+pub fn create_name(rng: &RandGenerator, num: usize) -> String {
     let names_list: Vec<&str> = vec![
         "am","af", "ax", "ar", "av", "al", "aq", "ak", "ar", "at",
         "cu", "ca", "co", "cy", "cu", "ce", "co", "cv", "ce", "cd", "cf", "cf", "ct", "ci", "cj", "ck", "cl", "cr", "cs", "cz", "cw", "cm", "cu", "cp",
@@ -205,7 +237,7 @@ pub fn create_name(num: usize) -> String {
     let mut name = String::new();
     let size = names_list.len();
     for locus in 0..num {
-        let i = rand::gen_range(0, size);
+        let i = rng.gen_range(0, size);
         let voice = names_list.get(i).unwrap();
         name.insert_str(locus*2, voice);
     }
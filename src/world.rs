@@ -0,0 +1,909 @@
+#![allow(unused)]
+
+use crate::quant::Quant;
+use crate::joint::MyJoint;
+use crate::interaction::*;
+use crate::grid::CellGrid;
+use crate::matter::*;
+use crate::molecule::*;
+use crate::tracker::MoleculeTracker;
+use crate::census::Census;
+use crate::util::*;
+use crate::physics::*;
+use crate::collector::*;
+use crate::globals::*;
+use crate::color::BLUE;
+use glam::Vec2;
+use quad_rand::RandGenerator;
+use rapier2d::na::Point2;
+use rapier2d::na::Vector2;
+use rapier2d::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::f32::consts::PI;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+
+/// Numbers describing the world at one moment, see `World::get_stats`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WorldStats {
+    pub sim_time: f64,
+    pub quants: usize,
+    pub bonds: usize,
+    /// Connected components of the bond graph, single quants included.
+    pub molecules: usize,
+    /// Number of quants in the biggest molecule.
+    pub largest_molecule: usize,
+    /// Species with at least one molecule present.
+    pub species: usize,
+    pub kinetic_energy: f32,
+    pub momentum: Vec2,
+}
+
+impl WorldStats {
+
+    pub fn csv_header() -> &'static str {
+        return "sim_time,quants,bonds,molecules,largest_molecule,species,kinetic_energy,momentum_x,momentum_y";
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        return format!("{:.4},{},{},{},{},{},{:.3},{:.3},{:.3}", self.sim_time, self.quants, self.bonds, self.molecules,
+            self.largest_molecule, self.species, self.kinetic_energy, self.momentum.x, self.momentum.y);
+    }
+}
+
+/// Windowless simulation core: physics world, quants and settings.
+/// Can be stepped and queried without macroquad window or egui context.
+/// All randomness goes through `rng` seeded with `Settings::seed`,
+/// so the same seed and the same inputs give the same world after N steps.
+pub struct World {
+    pub settings: Settings,
+    pub physics: Physics,
+    pub quants: QuantumCollector,
+    pub joints: JointCollector,
+    pub elements: ElementsBox,
+    pub molecules: MoleculesBox,
+    /// Molecules found in the bond graph after the last step.
+    pub tracker: MoleculeTracker,
+    /// Species of molecules seen since the world started.
+    pub census: Census,
+    pub interactions: InteractionMatrix,
+    /// Cohesion of every spawned liquid, acting only between quants of the same liquid.
+    pub cohesion: Vec<ForceLaw>,
+    pub sim_time: f64,
+    pub rng: RandGenerator,
+    /// Print bond events to stdout.
+    pub verbose: bool,
+    /// Fixed body holding wall colliders in `BoundaryMode::Walls`.
+    walls: Option<RigidBodyHandle>,
+    /// Body pulled by spring force towards target point.
+    drag: Option<(RigidBodyHandle, Vec2)>,
+}
+
+impl World {
+
+    /// Empty world with built-in element table and walls or periodic edges as set in `settings`.
+    pub fn new(settings: Settings) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(settings.seed);
+        let elements = ElementsBox::new();
+        let interactions = InteractionMatrix::new(elements.count());
+        let mut world = Self {
+            settings,
+            physics: Physics::new(),
+            quants: QuantumCollector::new(),
+            joints: JointCollector::new(),
+            elements,
+            molecules: MoleculesBox::new(),
+            tracker: MoleculeTracker::new(),
+            census: Census::new(),
+            interactions,
+            cohesion: vec![],
+            sim_time: 0.0,
+            rng,
+            verbose: true,
+            walls: None,
+            drag: None,
+        };
+        world.update_physics_params();
+        world.update_boundary();
+        return world;
+    }
+
+    /// Advances the world by one physics step of `Settings::dt` seconds: forces, bonds, molecules and census.
+    pub fn step(&mut self) {
+        self.check_quants_num();
+        self.apply_forces();
+        self.physics.step_physics();
+        self.check_bonds();
+        self.form_bonds();
+        if self.settings.boundary == BoundaryMode::Periodic {
+            self.wrap_molecules();
+        }
+        self.update_quants();
+        self.update_joints();
+        self.sim_time += self.physics.get_dt() as f64;
+        self.update_molecules();
+        debug_assert_eq!(self.check_consistency(), Ok(()));
+    }
+
+    /// Finds molecules in the bond graph and records their species in the census.
+    /// Done by every step; a freshly built or loaded world needs it once before its stats are read.
+    pub fn update_molecules(&mut self) {
+        self.tracker.update(&self.quants, &self.physics, self.elements.count(), self.sim_time, self.get_period());
+        self.census.update(&self.tracker, &self.elements, self.sim_time);
+    }
+
+    /// Removes quant together with its rigid body, collider and all its bonds.
+    /// Returns false if there is no such quant.
+    pub fn despawn_quant(&mut self, rbh: RigidBodyHandle) -> bool {
+        let bounds = match self.quants.get(rbh) {
+            Some(quant) => quant.bounds.clone(),
+            None => return false,
+        };
+        for handle in bounds {
+            self.remove_bond(handle);
+        }
+        self.quants.remove(rbh);
+        self.physics.remove_physics_object(rbh);
+        if self.drag.is_some_and(|(dragged, _)| dragged == rbh) {
+            self.drag = None;
+        }
+        return true;
+    }
+
+    /// Checks that quants, bonds and rapier sets describe the same objects:
+    /// every quant has its body with one collider, every bond its joint, and nothing else exists
+    /// in rapier besides walls.
+    pub fn check_consistency(&self) -> Result<(), String> {
+        let walls_colliders = match self.walls.and_then(|walls| self.physics.rigid_bodies.get(walls)) {
+            Some(rb) => rb.colliders().len(),
+            None => 0,
+        };
+        let walls_num = if self.walls.is_some() { 1 } else { 0 };
+        if self.physics.rigid_bodies.len() != self.quants.count() + walls_num {
+            return Err(format!("{} bodies for {} quants", self.physics.rigid_bodies.len(), self.quants.count()));
+        }
+        if self.physics.colliders.len() != self.quants.count() + walls_colliders {
+            return Err(format!("{} colliders for {} quants", self.physics.colliders.len(), self.quants.count()));
+        }
+        if self.physics.impulse_joint_set.len() != self.joints.count() {
+            return Err(format!("{} joints for {} bonds", self.physics.impulse_joint_set.len(), self.joints.count()));
+        }
+        for (rbh, quant) in self.quants.get_iter() {
+            match self.physics.rigid_bodies.get(*rbh) {
+                Some(rb) if rb.colliders().len() == 1 => {},
+                Some(rb) => return Err(format!("quant {:016X} has {} colliders", quant.key, rb.colliders().len())),
+                None => return Err(format!("quant {:016X} has no body", quant.key)),
+            }
+            for handle in quant.bounds.iter() {
+                if self.joints.get(*handle).is_none() || self.physics.impulse_joint_set.get(*handle).is_none() {
+                    return Err(format!("quant {:016X} has removed bond", quant.key));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// Removes all quants and bonds and restarts the clock. Physics is rebuilt from scratch, so no island,
+    /// broad phase or contact state of removed bodies survives the reset. Handle generations restart too,
+    /// so handles kept from before the reset may point at new bodies and must be dropped.
+    pub fn reset(&mut self) {
+        self.sim_time = 0.0;
+        self.physics = Physics::new();
+        self.update_physics_params();
+        self.quants = QuantumCollector::new();
+        self.joints = JointCollector::new();
+        self.cohesion.clear();
+        self.tracker.clear();
+        self.census.clear();
+        self.rng.srand(self.settings.seed);
+        self.walls = None;
+        self.drag = None;
+        self.update_boundary();
+    }
+
+    /// Replaces settings of running world. New seed takes effect after `reset`.
+    pub fn apply_settings(&mut self, settings: Settings) {
+        let rebuild_walls = settings.boundary != self.settings.boundary
+            || settings.world_w != self.settings.world_w || settings.world_h != self.settings.world_h;
+        self.settings = settings;
+        self.update_physics_params();
+        if rebuild_walls {
+            self.update_boundary();
+        }
+    }
+
+    fn update_physics_params(&mut self) {
+        let [gx, gy] = self.settings.gravity;
+        self.physics.set_gravity(Vec2::new(gx, gy));
+        self.physics.set_dt(self.settings.dt);
+        self.physics.set_solver_iterations(self.settings.solver_iterations);
+    }
+
+    pub fn set_boundary(&mut self, boundary: BoundaryMode) {
+        self.settings.boundary = boundary;
+        self.update_boundary();
+    }
+
+    /// Recreates walls for current boundary mode and world size.
+    pub fn update_boundary(&mut self) {
+        if let Some(walls) = self.walls.take() {
+            self.physics.remove_physics_object(walls);
+        }
+        if self.settings.boundary == BoundaryMode::Walls {
+            self.walls = Some(self.physics.add_walls(self.world_size()));
+        }
+    }
+
+    /// World size if the world is periodic, used for minimum-image distances.
+    pub fn get_period(&self) -> Option<Vec2> {
+        match self.settings.boundary {
+            BoundaryMode::Periodic => Some(self.world_size()),
+            _ => None,
+        }
+    }
+
+    /// Loads element table from `ELEMENTS_PATH` and molecule library from `MOLECULES_PATH`.
+    /// Missing or broken files are reported and the built-in defaults are kept.
+    pub fn load_assets(&mut self) {
+        match ElementsBox::load(ELEMENTS_PATH) {
+            Ok(elements) => self.set_elements(elements),
+            Err(e) => println!("[ELEMENTS]: can't load {}: {}", ELEMENTS_PATH, e),
+        }
+        match MoleculesBox::load_dir(MOLECULES_PATH, &self.elements) {
+            Ok(molecules) => self.molecules = molecules,
+            Err(e) => println!("[MOLECULES]: can't read {}: {}", MOLECULES_PATH, e),
+        }
+    }
+
+    /// Replaces element table, resizing interaction matrix to the new number of species.
+    pub fn set_elements(&mut self, elements: ElementsBox) {
+        self.interactions.resize(elements.count());
+        self.elements = elements;
+    }
+
+    pub fn world_size(&self) -> Vec2 {
+        return Vec2::new(self.settings.world_w as f32, self.settings.world_h as f32);
+    }
+
+    pub fn get_quant(&self, handle: RigidBodyHandle) -> Option<&Quant> {
+        return self.quants.get(handle);
+    }
+
+    pub fn get_physics_data(&self, handle: RigidBodyHandle) -> Result<PhysicsData, PhysicsError> {
+        return self.physics.get_physics_data(handle);
+    }
+
+    /// All quants with handles of their rigid bodies.
+    pub fn get_quants_iter(&self) -> impl Iterator<Item = (RigidBodyHandle, &Quant)> {
+        return self.quants.get_iter().map(|(rbh, quant)| (*rbh, quant));
+    }
+
+    pub fn quants_num(&self) -> usize {
+        return self.quants.count();
+    }
+
+    pub fn bodies_num(&self) -> usize {
+        return self.physics.get_physics_obj_num();
+    }
+
+    pub fn joints_num(&self) -> usize {
+        return self.physics.impulse_joint_set.len();
+    }
+
+    /// Hash of positions and velocities of all bodies, in rapier storage order.
+    /// Two runs with the same seed and inputs have the same checksum after N steps.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (_, rb) in self.physics.rigid_bodies.iter() {
+            let iso = rb.position();
+            let values = [iso.translation.x, iso.translation.y, iso.rotation.angle(), rb.linvel().x, rb.linvel().y, rb.angvel()];
+            for v in values.iter() {
+                v.to_bits().hash(&mut hasher);
+            }
+        }
+        return hasher.finish();
+    }
+
+    /// Summary of the world state after the last step.
+    pub fn get_stats(&self) -> WorldStats {
+        let mut kinetic_energy = 0.0;
+        let mut momentum = Vec2::ZERO;
+        // summed in arena order, the quants map iterates differently in every process
+        for (rbh, rb) in self.physics.rigid_bodies.iter() {
+            if self.quants.get(rbh).is_some() {
+                kinetic_energy += rb.kinetic_energy();
+                momentum += Vec2::new(rb.linvel().x, rb.linvel().y) * rb.mass();
+            }
+        }
+        let largest_molecule = self.tracker.get_iter().map(|m| m.size()).max().unwrap_or(0);
+        return WorldStats {
+            sim_time: self.sim_time,
+            quants: self.quants.count(),
+            bonds: self.joints.count(),
+            molecules: self.tracker.count(),
+            largest_molecule,
+            species: self.census.get_iter().filter(|s| s.count > 0).count(),
+            kinetic_energy,
+            momentum,
+        };
+    }
+
+    /// Quant under the given world point, also one spawned while paused.
+    /// In periodic world the point is wrapped into the period and its images across the edges are tried too.
+    pub fn pick_quant(&mut self, point: Vec2) -> Option<RigidBodyHandle> {
+        self.physics.update_queries();
+        let images = match self.get_period() {
+            Some(size) => {
+                let p = wrap_around(&point, size);
+                let mut images = vec![p];
+                for (x, y) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                    images.push(p + Vec2::new(x as f32, y as f32) * size);
+                }
+                images
+            },
+            None => vec![point],
+        };
+        for image in images {
+            if let Some(rbh) = self.physics.pick_body(image).filter(|rbh| self.quants.get(*rbh).is_some()) {
+                return Some(rbh);
+            }
+        }
+        return None;
+    }
+
+    pub fn get_velocity(&self, handle: RigidBodyHandle) -> Option<(Vec2, f32)> {
+        let rb = self.physics.rigid_bodies.get(handle)?;
+        return Some((Vec2::new(rb.linvel().x, rb.linvel().y), rb.angvel()));
+    }
+
+    pub fn set_velocity(&mut self, handle: RigidBodyHandle, lin_vel: Vec2, ang_vel: f32) {
+        if let Some(rb) = self.physics.rigid_bodies.get_mut(handle) {
+            rb.set_linvel(Vector2::new(lin_vel.x, lin_vel.y), true);
+            rb.set_angvel(ang_vel, true);
+        }
+    }
+
+    /// Changes quant radius, its collider is scaled by the same factor.
+    pub fn set_quant_size(&mut self, handle: RigidBodyHandle, size: f32) {
+        let quant = match self.quants.get_mut(handle) {
+            Some(quant) if size > 0.0 && quant.size > 0.0 => quant,
+            _ => return,
+        };
+        let radius = match quant.shape.as_ball() {
+            Some(ball) => ball.radius * size / quant.size,
+            None => return,
+        };
+        quant.size = size;
+        quant.shape = SharedShape::ball(radius);
+        if let Some(rb) = self.physics.rigid_bodies.get(handle) {
+            if let Some(collider) = rb.colliders().first().and_then(|ch| self.physics.colliders.get_mut(*ch)) {
+                collider.set_shape(quant.shape.clone());
+            }
+        }
+    }
+
+    /// Frozen body keeps colliding but all its axes are locked, so forces don't move it.
+    /// Dragging moves it straight to the drag target instead.
+    pub fn is_frozen(&self, handle: RigidBodyHandle) -> bool {
+        match self.physics.rigid_bodies.get(handle) {
+            Some(rb) => rb.locked_axes() == LockedAxes::all(),
+            None => false,
+        }
+    }
+
+    pub fn set_frozen(&mut self, handle: RigidBodyHandle, frozen: bool) {
+        if let Some(rb) = self.physics.rigid_bodies.get_mut(handle) {
+            match frozen {
+                true => {
+                    rb.set_linvel(Vector2::zeros(), false);
+                    rb.set_angvel(0.0, false);
+                    rb.set_locked_axes(LockedAxes::all(), true);
+                },
+                false => rb.set_locked_axes(LockedAxes::empty(), true),
+            }
+        }
+    }
+
+    /// Other quants bonded directly with the quant.
+    pub fn get_bonded(&self, handle: RigidBodyHandle) -> Vec<RigidBodyHandle> {
+        let mut bonded: Vec<RigidBodyHandle> = vec![];
+        if let Some(quant) = self.quants.get(handle) {
+            for joint_handle in quant.bounds.iter() {
+                if let Some(joint) = self.physics.impulse_joint_set.get(*joint_handle) {
+                    bonded.push(if joint.body1 == handle { joint.body2 } else { joint.body1 });
+                }
+            }
+        }
+        return bonded;
+    }
+
+    /// Starts or moves dragging of a body towards `target`.
+    pub fn set_drag(&mut self, handle: RigidBodyHandle, target: Vec2) {
+        self.drag = Some((handle, target));
+    }
+
+    pub fn clear_drag(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn get_drag(&self) -> Option<(RigidBodyHandle, Vec2)> {
+        return self.drag;
+    }
+
+    /// Damped spring between dragged body and its target, scaled by body mass.
+    /// A frozen body ignores forces, so it's moved to the target directly.
+    fn apply_drag(&mut self) {
+        let (rbh, target) = match self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        // cursor may be outside of periodic world, the body is pulled towards its nearest image
+        let period = self.get_period();
+        let to_target = |pos: Vec2| match period {
+            Some(size) => min_image(target - pos, size),
+            None => target - pos,
+        };
+        match self.physics.rigid_bodies.get_mut(rbh) {
+            Some(rb) if rb.locked_axes() == LockedAxes::all() => {
+                let pos = matrix_to_vec2(rb.position().translation);
+                let target = pos + to_target(pos);
+                rb.set_translation(Vector2::new(target.x, target.y), true);
+            },
+            Some(rb) => {
+                let pos = matrix_to_vec2(rb.position().translation);
+                let vel = Vec2::new(rb.linvel().x, rb.linvel().y);
+                let force = rb.mass() * (DRAG_STIFFNESS * to_target(pos) - DRAG_DAMPING * vel);
+                rb.add_force(Vector2::new(force.x, force.y), true);
+            },
+            None => {
+                self.drag = None;
+            },
+        }
+    }
+
+    /// Quants connected with `start` through bonds, including `start` itself.
+    pub fn bonded_group(&self, start: RigidBodyHandle) -> Vec<RigidBodyHandle> {
+        let mut group: Vec<RigidBodyHandle> = vec![start];
+        let mut visited: HashSet<RigidBodyHandle> = HashSet::from([start]);
+        let mut i = 0;
+        while i < group.len() {
+            if let Some(quant) = self.quants.get(group[i]) {
+                for handle in quant.bounds.iter() {
+                    if let Some(joint) = self.physics.impulse_joint_set.get(*handle) {
+                        for rbh in [joint.body1, joint.body2] {
+                            if visited.insert(rbh) {
+                                group.push(rbh);
+                            }
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+        return group;
+    }
+
+    /// Moves molecules whose center left the world to the opposite side.
+    /// Bonded quants are shifted together, so molecules stay intact across the seam.
+    fn wrap_molecules(&mut self) {
+        let size = self.world_size();
+        let mut visited: HashSet<RigidBodyHandle> = HashSet::new();
+        let handles: Vec<RigidBodyHandle> = self.physics.rigid_bodies.iter().map(|(rbh, _)| rbh).collect();
+        for rbh in handles {
+            if visited.contains(&rbh) || self.quants.get(rbh).is_none() {
+                continue;
+            }
+            let group = self.bonded_group(rbh);
+            let mut center = Vec2::ZERO;
+            for member in group.iter() {
+                visited.insert(*member);
+                if let Some(pos) = self.physics.get_object_position(*member) {
+                    center += pos;
+                }
+            }
+            center /= group.len() as f32;
+            let shift = wrap_around(&center, size) - center;
+            if shift == Vec2::ZERO {
+                continue;
+            }
+            for member in group.iter() {
+                if let Some(rb) = self.physics.rigid_bodies.get_mut(*member) {
+                    let pos = rb.translation() + Vector2::new(shift.x, shift.y);
+                    rb.set_translation(pos, true);
+                }
+            }
+        }
+    }
+
+    fn update_quants(&mut self) {
+        for (_, quant) in self.quants.get_iter_mut() {
+            quant.update(&mut self.physics);
+        }
+    }
+
+    /// Applies custom inter-particle forces as user forces on rigid bodies.
+    /// Forces set in the previous step are cleared first.
+    fn apply_forces(&mut self) {
+        for (_, rb) in self.physics.rigid_bodies.iter_mut() {
+            rb.reset_forces(false);
+        }
+        if self.interactions.is_active() || self.cohesion.iter().any(|law| law.is_active()) {
+            self.apply_interactions();
+        }
+        self.physics.update_grav(self.settings.gravity_mode, self.settings.grav_theta, self.get_period());
+        self.apply_drag();
+    }
+
+    fn apply_interactions(&mut self) {
+        let forces = self.compute_interactions();
+        for (rbh, force) in forces.iter() {
+            if let Some(rb) = self.physics.rigid_bodies.get_mut(*rbh) {
+                rb.add_force(Vector2::new(force.x, force.y), true);
+            }
+        }
+    }
+
+    /// Pairwise forces between species from the interaction matrix, plus cohesion inside liquids.
+    /// Neighbors are found with a cell grid sized to the longest active range,
+    /// so cost grows close to linearly with number of quants for short-range laws.
+    pub fn compute_interactions(&self) -> Vec<(RigidBodyHandle, Vec2)> {
+        let mut handles: Vec<RigidBodyHandle> = vec![];
+        let mut positions: Vec<Vec2> = vec![];
+        let mut elements: Vec<usize> = vec![];
+        let mut liquids: Vec<Option<usize>> = vec![];
+        for (rbh, rb) in self.physics.rigid_bodies.iter() {
+            if let Some(quant) = self.quants.get(rbh) {
+                handles.push(rbh);
+                positions.push(matrix_to_vec2(rb.position().translation));
+                elements.push(quant.element);
+                liquids.push(quant.cohesion);
+            }
+        }
+        let mut range = self.interactions.max_range();
+        for law in self.cohesion.iter().filter(|law| law.is_active()) {
+            range = range.max(law.range);
+        }
+        let period = self.get_period();
+        let grid = match period {
+            Some(size) => CellGrid::new_periodic(&positions, range, size),
+            None => CellGrid::new(&positions, range),
+        };
+        let mut forces: Vec<(RigidBodyHandle, Vec2)> = Vec::with_capacity(handles.len());
+        for i in 0..handles.len() {
+            let pos1 = positions[i];
+            let mut force = Vec2::ZERO;
+            grid.for_each_neighbor(pos1, |j| {
+                if i == j {
+                    return;
+                }
+                let law = self.interactions.get(elements[i], elements[j]);
+                let cohesion = match (liquids[i], liquids[j]) {
+                    (Some(a), Some(b)) if a == b => self.cohesion.get(a),
+                    _ => None,
+                };
+                if law.is_none() && cohesion.is_none() {
+                    return;
+                }
+                let d = match period {
+                    Some(size) => min_image(positions[j] - pos1, size),
+                    None => positions[j] - pos1,
+                };
+                let dist = d.length();
+                if dist <= 0.0 {
+                    return;
+                }
+                let magnitude = law.map(|l| l.get_force(dist)).unwrap_or(0.0) + cohesion.map(|l| l.get_force(dist)).unwrap_or(0.0);
+                force += d / dist * magnitude;
+            });
+            forces.push((handles[i], force));
+        }
+        return forces;
+    }
+
+    fn update_joints(&mut self) {
+        for (_, joint) in self.joints.get_iter_mut() {
+            joint.update(&mut self.physics);
+        }
+    }
+
+    /// Creates bond between two quants and registers it in their `bounds` lists.
+    pub fn add_bond(&mut self, body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle, joint: impl Into<GenericJoint>) -> ImpulseJointHandle {
+        let bond = MyJoint::new_custom(body_handle1, body_handle2, joint.into(), BLUE, self.settings.bond_strength, self.settings.bond_max_extension, &mut self.physics);
+        let handle = self.joints.add_joint(bond);
+        for rbh in [body_handle1, body_handle2] {
+            if let Some(quant) = self.quants.get_mut(rbh) {
+                quant.bounds.push(handle);
+            }
+        }
+        return handle;
+    }
+
+    /// Removes bond and its rapier joint, both quants get the valence back.
+    pub fn remove_bond(&mut self, handle: ImpulseJointHandle) {
+        if let Some(joint) = self.physics.impulse_joint_set.remove(handle, true) {
+            for rbh in [joint.body1, joint.body2] {
+                if let Some(quant) = self.quants.get_mut(rbh) {
+                    quant.bounds.retain(|h| *h != handle);
+                }
+            }
+        }
+        self.joints.remove(handle);
+    }
+
+    fn check_bonds(&mut self) {
+        let mut broken: Vec<(ImpulseJointHandle, f32, f32)> = vec![];
+        // walked in arena order, the bonds map iterates differently in every process
+        // and removal order decides which joint slots rapier reuses
+        for (handle, _) in self.physics.impulse_joint_set.iter() {
+            let joint = match self.joints.get(handle) {
+                Some(joint) => joint,
+                None => continue,
+            };
+            if joint.is_overloaded(&self.physics) {
+                let (impulse, extension) = joint.get_strain(&self.physics);
+                broken.push((handle, impulse, extension));
+            }
+        }
+        for (handle, impulse, extension) in broken {
+            self.remove_bond(handle);
+            if self.verbose {
+                println!("[BOND]: broken at {:.2}s impulse: {:.1} extension: {:.1}", self.sim_time, impulse, extension);
+            }
+        }
+    }
+
+    fn are_bonded(&self, body_handle1: RigidBodyHandle, body_handle2: RigidBodyHandle) -> bool {
+        let quant = match self.quants.get(body_handle1) {
+            Some(quant) => quant,
+            None => return false,
+        };
+        for handle in quant.bounds.iter() {
+            if let Some(joint) = self.physics.impulse_joint_set.get(*handle) {
+                if joint.body1 == body_handle2 || joint.body2 == body_handle2 {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    fn has_free_valence(&self, body_handle: RigidBodyHandle) -> bool {
+        match self.quants.get(body_handle) {
+            Some(quant) => quant.bounds.len() < quant.bounds_num,
+            None => false,
+        }
+    }
+
+    /// Bonds quants which started touching during last step, if both have free valence
+    /// and their relative speed is below `Settings::bond_activation`.
+    fn form_bonds(&mut self) {
+        let contacts = std::mem::take(&mut self.physics.contacts);
+        for (rbh1, rbh2) in contacts.iter() {
+            if !self.has_free_valence(*rbh1) || !self.has_free_valence(*rbh2) || self.are_bonded(*rbh1, *rbh2) {
+                continue;
+            }
+            let (rb1, rb2) = match (self.physics.rigid_bodies.get(*rbh1), self.physics.rigid_bodies.get(*rbh2)) {
+                (Some(rb1), Some(rb2)) => (rb1, rb2),
+                _ => continue,
+            };
+            let rel_speed = (rb1.linvel() - rb2.linvel()).norm();
+            if rel_speed >= self.settings.bond_activation {
+                continue;
+            }
+            let p1 = rb1.position();
+            let p2 = rb2.position();
+            let vr = (p2.translation.vector - p1.translation.vector) / 2.0;
+            let axis1 = p1.rotation.inverse_transform_vector(&vr);
+            let axis2 = p2.rotation.inverse_transform_vector(&vr);
+            let bound = PrismaticJointBuilder::new(UnitVector::new_normalize(axis1)).local_axis2(UnitVector::new_normalize(axis2))
+                .local_anchor1(Point2::from(axis1)).local_anchor2(Point2::from(-axis2))
+                .limits([0.0, 1.0]).build();
+            self.add_bond(*rbh1, *rbh2, bound);
+            if self.verbose {
+                println!("[BOND]: formed at {:.2}s relative speed: {:.1}", self.sim_time, rel_speed);
+            }
+        }
+    }
+
+    fn check_quants_num(&mut self) {
+        if self.quants.count() < self.settings.quant_min_num {
+            if let Some(element) = self.elements.get(0) {
+                self.quants.add_many_quants(1, 0, element, &self.settings, &self.rng, &mut self.physics);
+            }
+        }
+    }
+
+    /// Spawns single quant of given element at given position.
+    pub fn add_quant_at(&mut self, position: Vec2, element_id: usize) -> Option<RigidBodyHandle> {
+        let element = self.elements.get(element_id)?;
+        let quant = Quant::new_custom(position, element_id, element, &self.rng, &mut self.physics);
+        return Some(self.quants.add_quant(quant));
+    }
+
+    /// Spawns single quant of given element at random position.
+    pub fn add_element(&mut self, element_id: usize) -> Option<RigidBodyHandle> {
+        let element = self.elements.get(element_id)?;
+        let quant = Quant::new(element_id, element, &self.settings, &self.rng, &mut self.physics);
+        return Some(self.quants.add_quant(quant));
+    }
+
+    fn random_center(&self) -> Vec2 {
+        let size = self.world_size();
+        return random_position(&self.rng, 100.0, 100.0) + Vec2::new(size.x/2.0-50.0, size.y/2.0-50.0);
+    }
+
+    pub fn add_some(&mut self, num: usize) {
+        for _ in 0..num {
+            let n = self.rng.gen_range(1, 6) as usize;
+            self.add_custom(n);
+        }
+    }
+
+    /// Fills region of the spec with solid, liquid or gas and returns spawned quants, fewer than
+    /// `spec.count` if the region can't hold them. Liquid cohesion is written into the interaction
+    /// matrix, so it acts on all quants of the element, not only on the new ones.
+    pub fn add_matter(&mut self, spec: &MatterSpec) -> Vec<RigidBodyHandle> {
+        let (radius, valence) = match self.elements.get(spec.element) {
+            Some(element) => (element.radius, element.valence),
+            None => return vec![],
+        };
+        let (points, spacing) = fill_region(spec.region, spec.count, 2.0 * radius, spec.get_lattice());
+        if points.len() < spec.count && self.verbose {
+            println!("[MATTER]: region holds only {} of {} quants", points.len(), spec.count);
+        }
+        if self.verbose {
+            if let Some(missing) = spec.get_missing_bonds(valence) {
+                println!("[MATTER]: valence {} leaves {} of {} lattice neighbors unbonded", valence, missing, spec.lattice.coordination());
+            }
+        }
+        let mut handles: Vec<RigidBodyHandle> = Vec::with_capacity(points.len());
+        for point in points.iter() {
+            let mut pos = *point;
+            if spec.state == MatterState::Gas {
+                let jitter = (spacing / 2.0 - radius).max(0.0);
+                pos += Vec2::new(random_unit(&self.rng), random_unit(&self.rng)) * jitter;
+            }
+            if let Some(rbh) = self.add_quant_at(pos, spec.element) {
+                handles.push(rbh);
+            }
+        }
+        match spec.state {
+            MatterState::Solid => self.bond_lattice(&handles, spacing),
+            MatterState::Liquid => {
+                // the element's own law stays, other quants of the element don't join the liquid
+                self.cohesion.push(ForceLaw { strength: spec.cohesion, range: 4.0 * radius, falloff: Falloff::Linear });
+                let liquid = self.cohesion.len() - 1;
+                for rbh in handles.iter() {
+                    if let Some(quant) = self.quants.get_mut(*rbh) {
+                        quant.cohesion = Some(liquid);
+                    }
+                }
+                self.add_thermal_velocities(&handles, spec.temperature);
+            },
+            MatterState::Gas => self.add_thermal_velocities(&handles, spec.temperature),
+        }
+        return handles;
+    }
+
+    /// Bonds lattice neighbors with fixed joints, as far as valence of both quants allows.
+    fn bond_lattice(&mut self, handles: &[RigidBodyHandle], spacing: f32) {
+        let positions: Vec<Vec2> = handles.iter().map(|rbh| self.physics.get_object_position(*rbh).unwrap_or(Vec2::NAN)).collect();
+        let max_dist = spacing * 1.1;
+        let grid = CellGrid::new(&positions, max_dist);
+        let mut pairs: Vec<(usize, usize)> = vec![];
+        for i in 0..positions.len() {
+            grid.for_each_neighbor(positions[i], |j| {
+                if i < j && positions[i].distance(positions[j]) < max_dist {
+                    pairs.push((i, j));
+                }
+            });
+        }
+        pairs.sort();
+        for (i, j) in pairs {
+            let (rbh1, rbh2) = (handles[i], handles[j]);
+            if !self.has_free_valence(rbh1) || !self.has_free_valence(rbh2) {
+                continue;
+            }
+            let vr = (positions[j] - positions[i]) / 2.0;
+            let bond = FixedJointBuilder::new().local_anchor1(Point2::new(vr.x, vr.y)).local_anchor2(Point2::new(-vr.x, -vr.y)).build();
+            self.add_bond(rbh1, rbh2, bond);
+        }
+    }
+
+    /// Sets random velocities with normal distributed components of given deviation.
+    fn add_thermal_velocities(&mut self, handles: &[RigidBodyHandle], temperature: f32) {
+        for rbh in handles.iter() {
+            let vel = Vec2::new(random_normal(&self.rng), random_normal(&self.rng)) * temperature;
+            self.set_velocity(*rbh, vel, 0.0);
+        }
+    }
+
+    pub fn add_triplet(&mut self) {
+        let center = self.random_center();
+        _ = self.spawn_molecule(&MoleculeTemplate::star("triplet", 3, "B", "G", STAR_ARM_LENGTH), center, 0.0);
+    }
+
+    pub fn add_hex(&mut self) {
+        let center = self.random_center();
+        _ = self.spawn_molecule(&MoleculeTemplate::star("hex", 6, "B", "G", STAR_ARM_LENGTH), center, 0.0);
+    }
+
+    pub fn add_custom(&mut self, n: usize) {
+        let center = self.random_center();
+        _ = self.spawn_molecule(&MoleculeTemplate::star("custom", n, "R", "H", STAR_ARM_LENGTH), center, 0.0);
+    }
+
+    /// Instantiates molecule template at `position` rotated by `rotation`.
+    /// Returns handles of spawned quants in template atom order; nothing is spawned if the template is invalid.
+    pub fn spawn_molecule(&mut self, template: &MoleculeTemplate, position: Vec2, rotation: f32) -> Result<Vec<RigidBodyHandle>, TemplateError> {
+        let element_ids = template.validate(&self.elements)?;
+        let positions = template.get_positions(position, rotation);
+        let mut handles: Vec<RigidBodyHandle> = Vec::with_capacity(positions.len());
+        for (pos, element_id) in positions.iter().zip(element_ids.iter()) {
+            if let Some(rbh) = self.add_quant_at(*pos, *element_id) {
+                handles.push(rbh);
+            }
+        }
+        for bond in template.bonds.iter() {
+            let joint = bond.build_joint(positions[bond.i], positions[bond.j]);
+            self.add_bond(handles[bond.i], handles[bond.j], joint);
+        }
+        return Ok(handles);
+    }
+
+    /// Spawns template from the molecule library at random place with random rotation.
+    pub fn spawn_library_molecule(&mut self, id: usize) -> Result<Vec<RigidBodyHandle>, TemplateError> {
+        let template = match self.molecules.get(id) {
+            Some(template) => template.clone(),
+            None => return Err(TemplateError::NotFound { id }),
+        };
+        let center = self.random_center();
+        let rotation = random_rotation(&self.rng);
+        return self.spawn_molecule(&template, center, rotation);
+    }
+
+    /// Template of the molecule containing given quant, with atoms relative to its center.
+    pub fn template_from_molecule(&self, handle: RigidBodyHandle, name: &str) -> MoleculeTemplate {
+        let mut template = MoleculeTemplate::new(name);
+        let group = self.bonded_group(handle);
+        let mut positions: Vec<Vec2> = group.iter().map(|rbh| self.physics.get_object_position(*rbh).unwrap_or(Vec2::ZERO)).collect();
+        if let Some(size) = self.get_period() {
+            for i in 1..positions.len() {
+                positions[i] = positions[0] + min_image(positions[i] - positions[0], size);
+            }
+        }
+        let center = positions.iter().fold(Vec2::ZERO, |sum, p| sum + *p) / positions.len() as f32;
+        for (rbh, pos) in group.iter().zip(positions.iter()) {
+            let symbol = self.quants.get(*rbh).and_then(|q| self.elements.get(q.element)).map(|e| e.symbol.clone()).unwrap_or_default();
+            template.add_atom(&symbol, *pos - center);
+        }
+        for (i, rbh) in group.iter().enumerate() {
+            let quant = match self.quants.get(*rbh) {
+                Some(quant) => quant,
+                None => continue,
+            };
+            for joint_handle in quant.bounds.iter() {
+                let joint = match self.physics.impulse_joint_set.get(*joint_handle) {
+                    Some(joint) if joint.body1 == *rbh => joint,
+                    _ => continue,
+                };
+                let j = match group.iter().position(|other| *other == joint.body2) {
+                    Some(j) => j,
+                    None => continue,
+                };
+                let (bond_type, axis) = match joint.data.locked_axes {
+                    axes if axes == JointAxesMask::LOCKED_FIXED_AXES => (BondType::Fixed, None),
+                    axes if axes == JointAxesMask::LOCKED_PRISMATIC_AXES => (BondType::Prismatic, Some(JointAxis::X)),
+                    _ => (BondType::Revolute, Some(JointAxis::AngX)),
+                };
+                let limits = axis.and_then(|axis| joint.data.limits(axis)).map(|l| [l.min, l.max]);
+                let rest_length = joint.data.local_frame1.translation.vector.norm() + joint.data.local_frame2.translation.vector.norm();
+                template.bonds.push(BondTemplate { i, j, joint: bond_type, rest_length: Some(rest_length), limits });
+            }
+        }
+        return template;
+    }
+
+}
@@ -0,0 +1,104 @@
+#![allow(clippy::needless_return)]
+
+use quad_rand::RandGenerator;
+use quantum::census::fingerprint;
+
+
+/// Atom labels and bonds.
+type Graph = (Vec<usize>, Vec<(usize, usize)>);
+
+/// Same graph with atoms renumbered by a random permutation and bonds shuffled and flipped.
+fn permute(labels: &[usize], edges: &[(usize, usize)], rng: &RandGenerator) -> Graph {
+    let n = labels.len();
+    let mut perm: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        perm.swap(i, rng.gen_range(0, i + 1));
+    }
+    let mut new_labels = vec![0; n];
+    for i in 0..n {
+        new_labels[perm[i]] = labels[i];
+    }
+    let mut new_edges: Vec<(usize, usize)> = edges.iter().map(|(a, b)| {
+        if rng.gen_range(0, 2) == 0 {
+            return (perm[*a], perm[*b]);
+        }
+        return (perm[*b], perm[*a]);
+    }).collect();
+    for i in (1..new_edges.len()).rev() {
+        new_edges.swap(i, rng.gen_range(0, i + 1));
+    }
+    return (new_labels, new_edges);
+}
+
+fn ring(n: usize) -> Vec<(usize, usize)> {
+    return (0..n).map(|i| (i, (i + 1) % n)).collect();
+}
+
+fn prism() -> Vec<(usize, usize)> {
+    return vec![(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (0, 3), (1, 4), (2, 5)];
+}
+
+fn k33() -> Vec<(usize, usize)> {
+    let mut edges = vec![];
+    for a in 0..3 {
+        for b in 3..6 {
+            edges.push((a, b));
+        }
+    }
+    return edges;
+}
+
+#[test]
+fn fingerprint_ignores_atom_order() {
+    let rng = RandGenerator::new();
+    rng.srand(11);
+    // branched chain with twin leaves, a ring with a tail and both WL-regular graphs
+    let graphs: Vec<Graph> = vec![
+        (vec![0, 0, 0, 1, 1, 1, 1, 2], vec![(0, 1), (1, 2), (0, 3), (0, 4), (2, 5), (2, 6), (1, 7)]),
+        (vec![0, 1, 0, 1, 0, 1, 2, 2], { let mut e = ring(6); e.extend([(0, 6), (6, 7)]); e }),
+        (vec![0; 6], prism()),
+        (vec![0; 6], k33()),
+        (vec![0, 1, 0, 1, 0, 1], prism()),
+    ];
+    for (labels, edges) in graphs.iter() {
+        let expected = fingerprint(labels, edges);
+        for _ in 0..20 {
+            let (labels, edges) = permute(labels, edges, &rng);
+            assert_eq!(fingerprint(&labels, &edges), expected);
+        }
+    }
+}
+
+#[test]
+fn regular_graphs_are_told_apart() {
+    let labels = vec![0; 6];
+    assert_ne!(fingerprint(&labels, &prism()), fingerprint(&labels, &k33()));
+    // two triangles against a hexagon, both 2-regular on six atoms
+    let triangles = vec![(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)];
+    assert_ne!(fingerprint(&labels, &triangles), fingerprint(&labels, &ring(6)));
+}
+
+#[test]
+fn labels_are_part_of_fingerprint() {
+    assert_ne!(fingerprint(&[0, 1, 1], &[(0, 1), (1, 2)]), fingerprint(&[1, 0, 1], &[(0, 1), (1, 2)]));
+}
+
+#[test]
+fn symmetric_molecules_stay_fast() {
+    // a long chain where every atom carries two twin leaves would branch 2^40 ways without pruning
+    let n = 40;
+    let mut labels = vec![0; n];
+    let mut edges: Vec<(usize, usize)> = (1..n).map(|i| (i - 1, i)).collect();
+    for i in 0..n {
+        for _ in 0..2 {
+            labels.push(1);
+            edges.push((i, labels.len() - 1));
+        }
+    }
+    let rng = RandGenerator::new();
+    rng.srand(3);
+    let (permuted_labels, permuted_edges) = permute(&labels, &edges, &rng);
+    assert_eq!(fingerprint(&labels, &edges), fingerprint(&permuted_labels, &permuted_edges));
+    let big_ring = ring(200);
+    assert_eq!(fingerprint(&[0; 200], &big_ring), fingerprint(&[0; 200], &permute(&[0; 200], &big_ring, &rng).1));
+}
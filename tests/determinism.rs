@@ -0,0 +1,49 @@
+#![allow(clippy::needless_return)]
+
+use quantum::scenario::Scenario;
+use quantum::world::World;
+use rapier2d::prelude::ImpulseJointHandle;
+
+
+const SCENARIO_PATH: &str = "assets/scenarios/primordial.json";
+const STEPS: usize = 300;
+
+/// Builds the example scenario with bonds loose enough to break within `STEPS`.
+fn make_world(seed: u64) -> World {
+    let scenario = Scenario::load(SCENARIO_PATH).unwrap();
+    let mut settings = scenario.settings;
+    settings.seed = seed;
+    settings.bond_max_extension = 5.0;
+    let mut world = scenario.build(settings).unwrap();
+    world.verbose = false;
+    return world;
+}
+
+/// Steps the world and returns its checksum and the number of bonds that broke.
+fn run(world: &mut World) -> (u64, usize) {
+    let mut broken = 0;
+    for _ in 0..STEPS {
+        // a reused joint slot gets a new generation, so old handles never come back
+        let bonds: Vec<ImpulseJointHandle> = world.physics.impulse_joint_set.iter().map(|(handle, _)| handle).collect();
+        world.step();
+        broken += bonds.iter().filter(|handle| world.physics.impulse_joint_set.get(**handle).is_none()).count();
+    }
+    return (world.checksum(), broken);
+}
+
+#[test]
+fn same_seed_gives_same_world() {
+    // every world gets its own hash map keys, so iteration order differs between the two like between processes
+    let (checksum1, broken1) = run(&mut make_world(7));
+    let (checksum2, broken2) = run(&mut make_world(7));
+    assert!(broken1 > 1, "{} bonds broke in {} steps", broken1, STEPS);
+    assert_eq!(broken1, broken2);
+    assert_eq!(checksum1, checksum2);
+}
+
+#[test]
+fn different_seeds_give_different_worlds() {
+    let (checksum1, _) = run(&mut make_world(7));
+    let (checksum2, _) = run(&mut make_world(8));
+    assert_ne!(checksum1, checksum2);
+}
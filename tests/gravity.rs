@@ -0,0 +1,83 @@
+#![allow(clippy::needless_return)]
+
+use quantum::globals::*;
+use glam::Vec2;
+use quantum::physics::GravityMode;
+use quantum::quadtree::QuadTree;
+use quantum::world::World;
+
+
+const QUANTS_NUM: usize = 2_000;
+/// World area per quant, same density as in `quantum-cli bench`.
+const AREA_PER_QUANT: f32 = 900.0;
+
+fn make_world() -> World {
+    let side = (QUANTS_NUM as f32 * AREA_PER_QUANT).sqrt();
+    let settings = Settings {
+        seed: 1,
+        world_w: side as i32,
+        world_h: side as i32,
+        quant_min_num: 0,
+        bond_activation: 0.0,
+        gravity_mode: GravityMode::BarnesHut,
+        ..Default::default()
+    };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let elements_num = world.elements.count();
+    for i in 0..QUANTS_NUM {
+        world.add_element(i % elements_num);
+    }
+    world.step();
+    return world;
+}
+
+#[test]
+fn barnes_hut_matches_brute_force() {
+    let world = make_world();
+    let exact = world.physics.compute_grav(None);
+    let approx = world.physics.compute_grav_barnes_hut(world.settings.grav_theta, None);
+    assert_eq!(exact.len(), QUANTS_NUM);
+    assert_eq!(exact.len(), approx.len());
+    let mut err_sum = 0.0;
+    let mut force_sum = 0.0;
+    for ((h1, f1), (h2, f2)) in exact.iter().zip(approx.iter()) {
+        assert_eq!(h1, h2);
+        err_sum += (*f1 - *f2).length_squared();
+        force_sum += f1.length_squared();
+    }
+    // near the center of a uniform cloud forces almost cancel, so the error is measured against rms force
+    let err_rms = (err_sum / force_sum.max(f32::EPSILON)).sqrt();
+    assert!(err_rms <= GRAV_MAX_ERROR, "barnes-hut error {} above {}", err_rms, GRAV_MAX_ERROR);
+}
+
+#[test]
+fn barnes_hut_with_zero_theta_is_exact() {
+    let world = make_world();
+    let exact = world.physics.compute_grav(None);
+    let approx = world.physics.compute_grav_barnes_hut(0.0, None);
+    for ((_, f1), (_, f2)) in exact.iter().zip(approx.iter()) {
+        assert!((*f1 - *f2).length() <= 1e-3 * f1.length().max(1.0), "{} vs {}", f1, f2);
+    }
+}
+
+#[test]
+fn coincident_bodies_exclude_only_themselves() {
+    // bodies stacked at one point share a leaf at max depth
+    let mut positions: Vec<Vec2> = (0..50).map(|i| Vec2::new((i * 37 % 101) as f32 * 3.0, (i * 53 % 97) as f32 * 2.0)).collect();
+    positions.extend([Vec2::new(40.0, 40.0); 4]);
+    positions.extend([Vec2::new(41.0, 40.0); 2]);
+    let masses: Vec<f32> = (0..positions.len()).map(|i| 1.0 + (i % 3) as f32).collect();
+    let tree = QuadTree::new(&positions, &masses);
+    let soft2 = GRAV_SOFTENING * GRAV_SOFTENING;
+    for (i, pos) in positions.iter().enumerate() {
+        let mut exact = Vec2::ZERO;
+        for (j, other) in positions.iter().enumerate().filter(|(j, _)| *j != i) {
+            let d = *pos - *other;
+            let r2 = d.length_squared() + soft2;
+            exact += d * masses[j] / (r2 * r2.sqrt());
+        }
+        let field = tree.get_field(*pos, i, 0.0, GRAV_SOFTENING, None);
+        assert!((field - exact).length() <= 1e-4 * exact.length().max(1e-3), "body {}: {} vs {}", i, field, exact);
+    }
+}
@@ -0,0 +1,83 @@
+#![allow(clippy::needless_return)]
+
+use glam::Vec2;
+use quad_rand::RandGenerator;
+use quantum::globals::*;
+use quantum::grid::CellGrid;
+use quantum::util::min_image;
+use quantum::world::World;
+
+
+const RANGE: f32 = 30.0;
+
+fn random_points(num: usize, size: Vec2, seed: u64) -> Vec<Vec2> {
+    let rng = RandGenerator::new();
+    rng.srand(seed);
+    return (0..num).map(|_| Vec2::new(rng.gen_range(0.0, size.x), rng.gen_range(0.0, size.y))).collect();
+}
+
+/// Every pair closer than `RANGE` must be visited by the grid, and no point twice.
+fn assert_finds_all_neighbors(grid: &CellGrid, points: &[Vec2], period: Option<Vec2>) {
+    for (i, p) in points.iter().enumerate() {
+        let mut found = vec![];
+        grid.for_each_neighbor(*p, |j| found.push(j));
+        let mut unique = found.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), found.len(), "point {} visited a neighbor twice", i);
+        for (j, q) in points.iter().enumerate() {
+            let d = match period {
+                Some(size) => min_image(*q - *p, size),
+                None => *q - *p,
+            };
+            if d.length() < RANGE {
+                assert!(found.contains(&j), "point {} at {:?} misses {} at {:?}", i, p, j, q);
+            }
+        }
+    }
+}
+
+#[test]
+fn bounded_grid_matches_brute_force() {
+    for (num, seed) in [(1, 1), (10, 2), (300, 3), (2000, 4)] {
+        // points spread past the origin, the grid is fitted to their bounds
+        let points: Vec<Vec2> = random_points(num, Vec2::new(600.0, 400.0), seed).iter().map(|p| *p - Vec2::new(200.0, 100.0)).collect();
+        let grid = CellGrid::new(&points, RANGE);
+        assert_finds_all_neighbors(&grid, &points, None);
+    }
+}
+
+#[test]
+fn periodic_grid_matches_brute_force() {
+    // a side that isn't a multiple of the range, and a world only a few cells wide
+    for (size, num, seed) in [(Vec2::new(610.0, 395.0), 2000, 5), (Vec2::new(70.0, 50.0), 40, 6), (Vec2::new(600.0, 400.0), 10, 7)] {
+        let points = random_points(num, size, seed);
+        let grid = CellGrid::new_periodic(&points, RANGE, size);
+        assert_finds_all_neighbors(&grid, &points, Some(size));
+    }
+}
+
+#[test]
+fn zero_cell_size_in_periodic_world() {
+    let points = vec![Vec2::new(10.0, 10.0), Vec2::new(500.0, 700.0), Vec2::new(990.0, 5.0)];
+    let grid = CellGrid::new_periodic(&points, 0.0, Vec2::new(1000.0, 1000.0));
+    let mut found = vec![];
+    grid.for_each_neighbor(points[0], |i| found.push(i));
+    assert!(found.contains(&0));
+}
+
+#[test]
+fn zero_range_law_in_periodic_world() {
+    let settings = Settings { seed: 4, quant_min_num: 0, boundary: BoundaryMode::Periodic, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    for e in 0..world.elements.count() {
+        world.add_element(e).unwrap();
+    }
+    let law = world.interactions.get_mut(0, 1).unwrap();
+    law.strength = 10.0;
+    law.range = 0.0;
+    assert_eq!(world.interactions.max_range(), 0.0);
+    assert!(world.compute_interactions().iter().all(|(_, force)| *force == Vec2::ZERO));
+    world.step();
+}
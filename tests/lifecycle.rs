@@ -0,0 +1,141 @@
+#![allow(clippy::needless_return)]
+
+use glam::Vec2;
+use quantum::globals::*;
+use quantum::molecule::MoleculeTemplate;
+use quantum::world::World;
+use rapier2d::prelude::RigidBodyHandle;
+
+
+fn make_world(quants_num: usize) -> World {
+    let settings = Settings {
+        seed: 1,
+        quant_min_num: 0,
+        bond_activation: 0.0,
+        ..Default::default()
+    };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let elements_num = world.elements.count();
+    for i in 0..quants_num {
+        world.add_element(i % elements_num);
+    }
+    return world;
+}
+
+/// Spawns `G-B-G` chains in a row and returns their handles.
+fn spawn_chains(world: &mut World, num: usize) -> Vec<Vec<RigidBodyHandle>> {
+    let chain = MoleculeTemplate::chain("chain", &["G", "B", "G"], 14.0);
+    let mut molecules = vec![];
+    for i in 0..num {
+        let handles = world.spawn_molecule(&chain, Vec2::new(50.0 + 40.0 * i as f32, 100.0), 0.0).unwrap();
+        molecules.push(handles);
+    }
+    return molecules;
+}
+
+#[test]
+fn spawn_keeps_sets_in_sync() {
+    let mut world = make_world(200);
+    spawn_chains(&mut world, 10);
+    assert_eq!(world.check_consistency(), Ok(()));
+    for _ in 0..10 {
+        world.step();
+    }
+    assert_eq!(world.check_consistency(), Ok(()));
+    assert_eq!(world.quants.count(), 230);
+    assert_eq!(world.joints_num(), 20);
+}
+
+#[test]
+fn despawn_removes_body_collider_and_bonds() {
+    let mut world = make_world(200);
+    let molecules = spawn_chains(&mut world, 10);
+    world.step();
+    // middle atoms take both bonds with them, ends of chains stay as single quants
+    for handles in molecules.iter() {
+        assert!(world.despawn_quant(handles[1]));
+        assert!(!world.despawn_quant(handles[1]));
+        assert!(world.get_bonded(handles[0]).iter().all(|rbh| *rbh != handles[1]));
+    }
+    assert_eq!(world.check_consistency(), Ok(()));
+    assert_eq!(world.joints_num(), 0);
+    let handles: Vec<_> = world.quants.get_iter().map(|(rbh, _)| *rbh).take(100).collect();
+    for rbh in handles {
+        world.despawn_quant(rbh);
+    }
+    world.step();
+    assert_eq!(world.check_consistency(), Ok(()));
+    assert_eq!(world.quants.count(), 120);
+}
+
+#[test]
+fn reset_clears_world_and_clock() {
+    let mut world = make_world(200);
+    spawn_chains(&mut world, 10);
+    for _ in 0..10 {
+        world.step();
+    }
+    world.reset();
+    assert_eq!(world.check_consistency(), Ok(()));
+    assert_eq!(world.quants.count(), 0);
+    assert_eq!(world.joints_num(), 0);
+    assert_eq!(world.sim_time, 0.0);
+    assert!(world.get_drag().is_none());
+    world.add_some(5);
+    world.step();
+    assert_eq!(world.check_consistency(), Ok(()));
+}
+
+#[test]
+fn frozen_quant_stays_until_dragged_or_unfrozen() {
+    let mut world = make_world(0);
+    world.apply_settings(Settings { gravity: [0.0, -200.0], ..world.settings });
+    let rbh = world.add_element(0).unwrap();
+    world.set_frozen(rbh, true);
+    let start = world.physics.get_object_position(rbh).unwrap();
+    for _ in 0..30 {
+        world.step();
+    }
+    assert!(world.is_frozen(rbh));
+    assert_eq!(world.physics.get_object_position(rbh).unwrap(), start);
+
+    let target = start + Vec2::new(40.0, 25.0);
+    world.set_drag(rbh, target);
+    world.step();
+    world.clear_drag();
+    assert!(world.physics.get_object_position(rbh).unwrap().distance(target) < 1e-3);
+    world.step();
+    assert!(world.physics.get_object_position(rbh).unwrap().distance(target) < 1e-3);
+
+    world.set_frozen(rbh, false);
+    assert!(!world.is_frozen(rbh));
+    for _ in 0..30 {
+        world.step();
+    }
+    assert!(world.physics.get_object_position(rbh).unwrap().y < target.y - 1.0);
+}
+
+#[test]
+fn pick_finds_quants_spawned_while_paused() {
+    let mut world = make_world(0);
+    let single = MoleculeTemplate::chain("single", &["G"], 14.0);
+    let rbh = world.spawn_molecule(&single, Vec2::new(200.0, 150.0), 0.0).unwrap()[0];
+    assert_eq!(world.pick_quant(Vec2::new(201.0, 151.0)), Some(rbh));
+    assert_eq!(world.pick_quant(Vec2::new(260.0, 150.0)), None);
+}
+
+#[test]
+fn pick_wraps_cursor_in_periodic_world() {
+    let mut world = make_world(0);
+    world.set_boundary(BoundaryMode::Periodic);
+    let size = world.get_period().unwrap();
+    let single = MoleculeTemplate::chain("single", &["G"], 14.0);
+    let rbh = world.spawn_molecule(&single, Vec2::new(2.0, 150.0), 0.0).unwrap()[0];
+    world.step();
+    let pos = world.physics.get_object_position(rbh).unwrap();
+    // the cursor past the right edge, and just left of the left edge over the part hanging across it
+    assert_eq!(world.pick_quant(pos + Vec2::new(size.x, 0.0)), Some(rbh));
+    assert_eq!(world.pick_quant(pos - Vec2::new(size.x, 0.0)), Some(rbh));
+    assert_eq!(world.pick_quant(Vec2::new(size.x - 1.0, pos.y)), Some(rbh));
+}
@@ -0,0 +1,111 @@
+#![allow(clippy::needless_return)]
+
+use quantum::globals::*;
+use glam::Vec2;
+use quantum::matter::{fill_region, region_capacity, Lattice, MatterSpec, MatterState};
+use quantum::snapshot::Snapshot;
+use quantum::util::Rect;
+use quantum::world::World;
+use rapier2d::prelude::RigidBodyHandle;
+
+
+fn inside(region: Rect, spacing: f32, x: f32, y: f32) -> bool {
+    let margin = spacing / 2.0 - 1e-3;
+    return x >= region.x + margin && x <= region.x + region.w - margin && y >= region.y && y <= region.y + region.h;
+}
+
+#[test]
+fn points_stay_inside_region() {
+    let region = Rect::new(50.0, 80.0, 200.0, 120.0);
+    for lattice in [Lattice::Square, Lattice::Hexagonal] {
+        for count in [1, 10, 50, 100, 1000] {
+            let (points, spacing) = fill_region(region, count, 12.0, lattice);
+            assert!(spacing >= 12.0);
+            assert_eq!(points.len(), count.min(region_capacity(region, 12.0, lattice)));
+            for p in points.iter() {
+                assert!(inside(region, spacing, p.x, p.y), "{:?} outside region for {} points", p, count);
+            }
+        }
+    }
+}
+
+#[test]
+fn fitting_count_is_placed_in_full() {
+    let region = Rect::new(0.0, 0.0, 300.0, 300.0);
+    for lattice in [Lattice::Square, Lattice::Hexagonal] {
+        let capacity = region_capacity(region, 12.0, lattice);
+        for count in [capacity / 3, capacity / 2, capacity - 1, capacity] {
+            assert_eq!(fill_region(region, count, 12.0, lattice).0.len(), count);
+        }
+    }
+}
+
+#[test]
+fn overfull_matter_is_clamped() {
+    let settings = Settings { seed: 2, quant_min_num: 0, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let spec = MatterSpec {
+        state: MatterState::Gas,
+        count: 500,
+        region: Rect::new(100.0, 100.0, 100.0, 100.0),
+        ..Default::default()
+    };
+    let radius = world.elements.get(spec.element).unwrap().radius;
+    let handles = world.add_matter(&spec);
+    assert_eq!(handles.len(), spec.get_capacity(radius));
+    assert!(handles.len() < spec.count);
+    assert_eq!(world.quants.count(), handles.len());
+}
+
+/// Force on the quant from the last `compute_interactions`.
+fn force_on(forces: &[(RigidBodyHandle, Vec2)], rbh: RigidBodyHandle) -> Vec2 {
+    return forces.iter().find(|(h, _)| *h == rbh).unwrap().1;
+}
+
+#[test]
+fn liquid_cohesion_acts_only_inside_liquid() {
+    let settings = Settings { seed: 4, quant_min_num: 0, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let spec = MatterSpec {
+        state: MatterState::Liquid,
+        count: 30,
+        region: Rect::new(100.0, 100.0, 120.0, 120.0),
+        temperature: 0.0,
+        ..Default::default()
+    };
+    let radius = world.elements.get(spec.element).unwrap().radius;
+    // same element just outside the region, well within cohesion range of the liquid
+    let lone = world.add_quant_at(Vec2::new(100.0 - radius, 160.0), spec.element).unwrap();
+    let liquid = world.add_matter(&spec);
+    let other = world.add_matter(&MatterSpec { region: Rect::new(220.0 + radius, 100.0, 120.0, 120.0), ..spec });
+    assert_eq!(world.interactions.get(spec.element, spec.element).unwrap().strength, 0.0);
+    assert_eq!(world.cohesion.len(), 2);
+
+    let forces = world.compute_interactions();
+    assert_eq!(force_on(&forces, lone), Vec2::ZERO);
+    assert!(liquid.iter().filter(|rbh| force_on(&forces, **rbh) != Vec2::ZERO).count() > liquid.len() / 2);
+    assert!(other.iter().all(|rbh| world.get_quant(*rbh).unwrap().cohesion == Some(1)));
+    // liquids don't pull each other across the gap, the left column of the right one is pulled only into it
+    let left_x = other.iter().map(|rbh| world.physics.get_object_position(*rbh).unwrap().x).fold(f32::INFINITY, f32::min);
+    for rbh in other.iter().filter(|rbh| world.physics.get_object_position(**rbh).unwrap().x < left_x + 1.0) {
+        assert!(force_on(&forces, *rbh).x > 0.0);
+    }
+
+    let loaded = Snapshot::from_world(&world).to_world().unwrap();
+    let mut loaded_forces: Vec<Vec2> = loaded.compute_interactions().iter().map(|(_, f)| *f).collect();
+    let mut forces: Vec<Vec2> = forces.iter().map(|(_, f)| *f).collect();
+    loaded_forces.sort_by(|a, b| a.x.total_cmp(&b.x));
+    forces.sort_by(|a, b| a.x.total_cmp(&b.x));
+    assert_eq!(loaded_forces, forces);
+}
+
+#[test]
+fn low_valence_solid_is_reported() {
+    let spec = MatterSpec { state: MatterState::Solid, lattice: Lattice::Hexagonal, ..Default::default() };
+    assert_eq!(spec.get_missing_bonds(3), Some(3));
+    assert_eq!(spec.get_missing_bonds(6), None);
+    assert_eq!(MatterSpec { lattice: Lattice::Square, ..spec }.get_missing_bonds(3), Some(1));
+    assert_eq!(MatterSpec { state: MatterState::Liquid, ..spec }.get_missing_bonds(1), None);
+}
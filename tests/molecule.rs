@@ -0,0 +1,58 @@
+#![allow(clippy::needless_return)]
+
+use glam::Vec2;
+use quantum::globals::*;
+use quantum::molecule::{BondType, MoleculeTemplate, TemplateError};
+use quantum::world::World;
+
+
+fn make_world() -> World {
+    let settings = Settings { seed: 5, quant_min_num: 0, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    return world;
+}
+
+#[test]
+fn touching_atoms_are_not_overlapping() {
+    let world = make_world();
+    // two G atoms bonded on contact, as measured in the running world
+    let touching = MoleculeTemplate::chain("pair", &["G", "G"], 12.599);
+    assert!(touching.validate(&world.elements).is_ok());
+    let overlapping = MoleculeTemplate::chain("pair", &["G", "G"], 5.0);
+    assert!(matches!(overlapping.validate(&world.elements), Err(TemplateError::Overlap { .. })));
+}
+
+#[test]
+fn molecule_from_running_world_validates() {
+    let mut world = make_world();
+    let chain = MoleculeTemplate::chain("chain", &["G", "B", "G"], 12.0);
+    let handles = world.spawn_molecule(&chain, Vec2::new(300.0, 300.0), 0.0).unwrap();
+    for _ in 0..60 {
+        world.step();
+    }
+    let template = world.template_from_molecule(handles[0], "saved");
+    assert_eq!(template.validate(&world.elements).map(|ids| ids.len()), Ok(3));
+}
+
+#[test]
+fn duplicate_bonds_are_rejected() {
+    let world = make_world();
+    let mut template = MoleculeTemplate::chain("pair", &["G", "G"], 14.0);
+    assert!(template.validate(&world.elements).is_ok());
+    template.add_bond(0, 1, BondType::Fixed, None);
+    assert_eq!(template.validate(&world.elements), Err(TemplateError::DuplicateBond { bond: 1, i: 0, j: 1 }));
+    template.bonds[1].i = 1;
+    template.bonds[1].j = 0;
+    assert_eq!(template.validate(&world.elements), Err(TemplateError::DuplicateBond { bond: 1, i: 1, j: 0 }));
+}
+
+#[test]
+fn template_names_stay_inside_library() {
+    let mut template = MoleculeTemplate::chain("water", &["G", "G"], 14.0);
+    assert_eq!(template.file_path(MOLECULES_PATH), Ok(format!("{}/water.json", MOLECULES_PATH)));
+    for name in ["", "  ", "../elements", "sub/water", "sub\\water", "..", "a..b"] {
+        template.name = name.to_string();
+        assert_eq!(template.file_path(MOLECULES_PATH), Err(TemplateError::BadName { name: name.to_string() }));
+    }
+}
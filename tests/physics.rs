@@ -0,0 +1,73 @@
+#![allow(clippy::needless_return)]
+
+use glam::Vec2;
+use quantum::physics::{GravityMode, Physics, PhysicsError, PhysicsProperities};
+use quantum::util::{make_regular_poly_indices, vec2_to_point2_collection};
+use rapier2d::prelude::{RigidBodyHandle, SharedShape, ShapeType};
+
+
+/// Physics with a few balls around (100, 100) and one more ball that is already removed.
+fn make_physics() -> (Physics, RigidBodyHandle) {
+    let mut physics = Physics::new();
+    for i in 0..5 {
+        let pos = Vec2::new(80.0 + 10.0 * i as f32, 100.0);
+        physics.add_dynamic(i, &pos, 0.0, SharedShape::ball(4.0), PhysicsProperities::default());
+    }
+    let stale = physics.add_dynamic(5, &Vec2::new(100.0, 110.0), 0.0, SharedShape::ball(4.0), PhysicsProperities::default());
+    physics.step_physics();
+    physics.remove_physics_object(stale);
+    physics.step_physics();
+    return (physics, stale);
+}
+
+fn add_square(physics: &mut Physics) -> RigidBodyHandle {
+    let points = vec2_to_point2_collection(&make_regular_poly_indices(4, 7.0).0);
+    let square = SharedShape::convex_hull(&points).unwrap();
+    return physics.add_dynamic(6, &Vec2::new(100.0, 90.0), 0.0, square, PhysicsProperities::default());
+}
+
+#[test]
+fn queries_on_removed_body_return_stale_handle() {
+    let (physics, stale) = make_physics();
+    let err = Some(PhysicsError::StaleHandle(stale));
+    assert_eq!(physics.get_physics_data(stale).err(), err);
+    assert_eq!(physics.get_object_size(stale).err(), err);
+    assert_eq!(physics.get_contacts_set(stale, 50.0).err(), err);
+    assert_eq!(physics.get_closed_agent(stale, 50.0).err(), err);
+    assert_eq!(physics.get_object_position(stale), None);
+}
+
+#[test]
+fn queries_on_live_body_succeed() {
+    let (physics, _) = make_physics();
+    let (rbh, _) = physics.rigid_bodies.iter().next().unwrap();
+    assert!(physics.get_physics_data(rbh).is_ok());
+    assert_eq!(physics.get_object_size(rbh), Ok(4.0));
+    assert!(!physics.get_contacts_set(rbh, 50.0).unwrap().is_empty());
+    assert!(physics.get_closed_agent(rbh, 50.0).unwrap().is_some());
+}
+
+#[test]
+fn non_ball_shape_is_unsupported_size() {
+    let (mut physics, _) = make_physics();
+    let square = add_square(&mut physics);
+    assert_eq!(physics.get_object_size(square), Err(PhysicsError::UnsupportedShape(ShapeType::ConvexPolygon)));
+    // neighbours of balls may be polygons, only the size query needs a ball
+    assert!(physics.get_contacts_set(square, 50.0).is_ok());
+    assert!(physics.get_closed_agent(square, 50.0).is_ok());
+}
+
+#[test]
+fn gravity_survives_polygon_and_removed_bodies() {
+    let (mut physics, _) = make_physics();
+    let square = add_square(&mut physics);
+    for mode in [GravityMode::BruteForce, GravityMode::BarnesHut] {
+        physics.update_grav(mode, 0.5, None);
+    }
+    physics.remove_physics_object(square);
+    for mode in [GravityMode::BruteForce, GravityMode::BarnesHut] {
+        physics.update_grav(mode, 0.5, None);
+    }
+    physics.step_physics();
+    assert_eq!(physics.rigid_bodies.len(), 5);
+}
@@ -0,0 +1,41 @@
+#![allow(clippy::needless_return)]
+
+use quantum::globals::*;
+use quantum::runner;
+use quantum::scenario::{Scenario, Spawn};
+use quantum::snapshot::Snapshot;
+use quantum::world::WorldStats;
+
+
+/// Quants of every element with bonding switched off, so molecules only change if counted wrong.
+fn make_scenario() -> Scenario {
+    let settings = Settings { seed: 6, quant_min_num: 0, bond_activation: 0.0, ..Default::default() };
+    let spawns = ["G", "B", "R"].iter().map(|e| Spawn::Element { element: e.to_string(), count: 20 }).collect();
+    return Scenario { settings, spawns, ..Default::default() };
+}
+
+fn assert_same_molecules(a: &WorldStats, b: &WorldStats) {
+    assert_eq!((a.quants, a.bonds), (b.quants, b.bonds));
+    assert_eq!((a.molecules, a.largest_molecule, a.species), (b.molecules, b.largest_molecule, b.species));
+}
+
+#[test]
+fn first_sample_counts_molecules() {
+    let scenario = make_scenario();
+    let mut world = scenario.build(scenario.settings).unwrap();
+    world.verbose = false;
+    let samples = runner::run(&mut world, 1, 1, |_| {});
+    assert_eq!(samples[0].stats.molecules, 60);
+    assert_eq!(samples[0].stats.species, 3);
+    assert_same_molecules(&samples[0].stats, &samples[1].stats);
+}
+
+#[test]
+fn loaded_world_counts_molecules() {
+    let scenario = make_scenario();
+    let mut world = scenario.build(scenario.settings).unwrap();
+    world.verbose = false;
+    world.step();
+    let loaded = Snapshot::from_world(&world).to_world().unwrap();
+    assert_same_molecules(&loaded.get_stats(), &world.get_stats());
+}
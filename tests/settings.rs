@@ -0,0 +1,37 @@
+#![allow(clippy::needless_return)]
+
+use quantum::globals::*;
+use quantum::physics::GravityMode;
+
+
+#[test]
+fn set_field_parses_json_values() {
+    let mut settings = Settings::default();
+    settings.set_field("bond_strength", "8000").unwrap();
+    settings.set_field("boundary", "Periodic").unwrap();
+    settings.set_field("gravity_mode", "\"BarnesHut\"").unwrap();
+    settings.set_field("gravity", "[0, -9.8]").unwrap();
+    assert_eq!(settings.bond_strength, 8000.0);
+    assert!(settings.boundary == BoundaryMode::Periodic);
+    assert!(settings.gravity_mode == GravityMode::BarnesHut);
+    assert_eq!(settings.gravity, [0.0, -9.8]);
+}
+
+#[test]
+fn set_field_rejects_unknown_names_and_bad_values() {
+    let mut settings = Settings::default();
+    assert!(settings.set_field("bond_strenght", "8000").is_err());
+    assert!(settings.set_field("quant_speed", "100").is_err());
+    assert!(settings.set_field("bond_strength", "strong").is_err());
+    assert!(settings.set_field("boundary", "Torus").is_err());
+    assert_eq!(settings.bond_strength, Settings::default().bond_strength);
+}
+
+#[test]
+fn old_preset_with_removed_fields_loads() {
+    let json = r#"{ "seed": 9, "quant_init_num": 2, "quant_speed": 100.0, "force": 500.0, "bond_activation": 12.0 }"#;
+    let settings: Settings = serde_json::from_str(json).unwrap();
+    assert_eq!(settings.seed, 9);
+    assert_eq!(settings.bond_activation, 12.0);
+    assert_eq!(settings.dt, Settings::default().dt);
+}
@@ -0,0 +1,125 @@
+#![allow(clippy::needless_return)]
+
+use quantum::globals::*;
+use glam::Vec2;
+use quantum::molecule::MoleculeTemplate;
+use quantum::physics::GravityMode;
+use quantum::snapshot::{Snapshot, SnapshotError};
+use quantum::world::World;
+use rapier2d::prelude::{GenericJoint, LockedAxes, RevoluteJointBuilder, RigidBodyHandle};
+
+
+/// Radius of the ball collider of the quant with given key.
+fn collider_radius(world: &World, key: u64) -> f32 {
+    let (rbh, _) = world.get_quants_iter().find(|(_, q)| q.key == key).unwrap();
+    let rb = world.physics.rigid_bodies.get(rbh).unwrap();
+    let collider = world.physics.colliders.get(rb.colliders()[0]).unwrap();
+    return collider.shape().as_ball().unwrap().radius;
+}
+
+fn find_body(world: &World, key: u64) -> RigidBodyHandle {
+    return world.get_quants_iter().find(|(_, q)| q.key == key).unwrap().0;
+}
+
+/// Goes through JSON like a saved file does.
+fn round_trip(world: &World) -> World {
+    let json = serde_json::to_string(&Snapshot::from_world(world)).unwrap();
+    let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+    return snapshot.to_world().unwrap();
+}
+
+#[test]
+fn collider_radius_and_frozen_state_survive_round_trip() {
+    let settings = Settings { seed: 3, quant_min_num: 0, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let spawned = world.add_element(0).unwrap();
+    let resized = world.add_element(1).unwrap();
+    let frozen = world.add_element(2).unwrap();
+    world.set_quant_size(resized, 3.0);
+    world.set_frozen(frozen, true);
+    world.step();
+    let keys: Vec<u64> = [spawned, resized, frozen].iter().map(|rbh| world.get_quant(*rbh).unwrap().key).collect();
+
+    let loaded = round_trip(&world);
+    for key in keys.iter() {
+        assert_eq!(collider_radius(&loaded, *key), collider_radius(&world, *key));
+    }
+    assert!(loaded.is_frozen(find_body(&loaded, keys[2])));
+    assert!(!loaded.is_frozen(find_body(&loaded, keys[0])));
+    assert_eq!(loaded.physics.rigid_bodies.get(find_body(&loaded, keys[2])).unwrap().locked_axes(), LockedAxes::all());
+}
+
+/// Rapier joint data and bond parameters of every joint, keyed by quant keys of its bodies.
+fn joints_by_keys(world: &World) -> Vec<((u64, u64), GenericJoint, [f32; 3])> {
+    let mut joints = vec![];
+    for (handle, joint) in world.physics.impulse_joint_set.iter() {
+        let keys = (world.get_quant(joint.body1).unwrap().key, world.get_quant(joint.body2).unwrap().key);
+        let bond = world.joints.get(handle).unwrap();
+        joints.push((keys, joint.data, [bond.strength, bond.max_extension, bond.rest_length]));
+    }
+    joints.sort_by_key(|(keys, _, _)| *keys);
+    return joints;
+}
+
+#[test]
+fn joints_limits_and_settings_survive_round_trip() {
+    let settings = Settings {
+        seed: 12,
+        quant_min_num: 0,
+        bond_max_extension: 25.0,
+        bond_activation: 0.0,
+        boundary: BoundaryMode::Periodic,
+        gravity_mode: GravityMode::BarnesHut,
+        grav_theta: 0.3,
+        gravity: [0.0, -4.0],
+        dt: 1.0 / 120.0,
+        ..Default::default()
+    };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let chain = MoleculeTemplate::chain("chain", &["G", "B", "G"], 14.0);
+    let handles = world.spawn_molecule(&chain, Vec2::new(300.0, 300.0), 0.0).unwrap();
+    let single = MoleculeTemplate::chain("single", &["G"], 14.0);
+    let lone = world.spawn_molecule(&single, Vec2::new(328.0, 300.0), 0.0).unwrap()[0];
+    let limited = RevoluteJointBuilder::new().local_anchor1([7.0, 0.0].into()).local_anchor2([-7.0, 0.0].into()).limits([-0.4, 0.6]);
+    let handle = world.add_bond(handles[2], lone, limited);
+    world.joints.get_mut(handle).unwrap().max_extension = 33.0;
+    for _ in 0..5 {
+        world.step();
+    }
+
+    let loaded = round_trip(&world);
+    let joints = joints_by_keys(&world);
+    assert_eq!(joints.len(), 3);
+    for ((keys, joint, bond), (loaded_keys, loaded_joint, loaded_bond)) in joints.iter().zip(joints_by_keys(&loaded).iter()) {
+        assert_eq!(keys, loaded_keys);
+        assert_eq!(bond, loaded_bond);
+        assert_eq!(joint.locked_axes, loaded_joint.locked_axes);
+        assert_eq!(joint.limit_axes, loaded_joint.limit_axes);
+        assert_eq!(joint.limits, loaded_joint.limits);
+        assert!((joint.local_frame1.translation.vector - loaded_joint.local_frame1.translation.vector).norm() < 1e-5);
+        assert!((joint.local_frame2.translation.vector - loaded_joint.local_frame2.translation.vector).norm() < 1e-5);
+    }
+    assert!(joints.iter().any(|(_, _, bond)| bond[1] == 33.0));
+    assert_eq!(serde_json::to_string(&loaded.settings).unwrap(), serde_json::to_string(&world.settings).unwrap());
+    assert_eq!(loaded.get_period(), world.get_period());
+    assert_eq!(loaded.sim_time, world.sim_time);
+}
+
+#[test]
+fn unknown_element_fails_to_load() {
+    let settings = Settings { seed: 3, quant_min_num: 0, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    let chain = MoleculeTemplate::chain("chain", &["G", "G"], 14.0);
+    world.spawn_molecule(&chain, Vec2::new(300.0, 300.0), 0.0).unwrap();
+    let mut snapshot = Snapshot::from_world(&world);
+    snapshot.quants[1].element = 99;
+    let key = snapshot.quants[1].key;
+    assert_eq!(snapshot.to_world().err(), Some(SnapshotError::UnknownElement { key, element: 99 }));
+
+    let mut snapshot = Snapshot::from_world(&world);
+    snapshot.quants.remove(0);
+    assert!(matches!(snapshot.to_world(), Err(SnapshotError::UnknownQuant(_))));
+}
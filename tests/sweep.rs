@@ -0,0 +1,42 @@
+#![allow(clippy::needless_return)]
+
+use quantum::globals::*;
+use quantum::scenario::{Scenario, Spawn};
+use quantum::sweep::{Param, RunResult, Sweep, Values};
+
+
+fn make_scenario() -> Scenario {
+    let settings = Settings { quant_min_num: 0, ..Default::default() };
+    let spawns = vec![Spawn::Element { element: String::from("G"), count: 40 }, Spawn::Stars { count: 4 }];
+    return Scenario { settings, spawns, ..Default::default() };
+}
+
+fn make_sweep(threads: usize) -> Sweep {
+    let seeds = Param { name: String::from("seed"), values: Values::Range { from: 1.0, to: 3.0, step: 1.0 } };
+    let activation = Param { name: String::from("bond_activation"), values: Values::List { values: vec![20.into(), 60.into()] } };
+    return Sweep { scenario: None, steps: 40, threads: Some(threads), params: vec![seeds, activation] };
+}
+
+/// Swept values and final stats of every run, in run order.
+fn rows(results: &[RunResult]) -> Vec<(String, String)> {
+    return results.iter().map(|r| (format!("{} {:?}", r.index, r.params), r.stats.as_ref().unwrap().to_csv_row())).collect();
+}
+
+#[test]
+fn sweep_results_do_not_depend_on_threads() {
+    let scenario = make_scenario();
+    let single = rows(&make_sweep(1).run(&scenario, |_, _| {}));
+    assert_eq!(single.len(), 6);
+    assert_eq!(rows(&make_sweep(1).run(&scenario, |_, _| {})), single);
+    assert_eq!(rows(&make_sweep(4).run(&scenario, |_, _| {})), single);
+    // the swept settings reach the runs
+    assert_ne!(single[0].1, single[2].1);
+}
+
+#[test]
+fn sweep_check_rejects_unknown_setting() {
+    let mut sweep = make_sweep(1);
+    sweep.params[1].name = String::from("bond_activaton");
+    assert!(sweep.check(&make_scenario()).is_err());
+    assert!(make_sweep(1).check(&make_scenario()).is_ok());
+}
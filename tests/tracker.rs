@@ -0,0 +1,88 @@
+#![allow(clippy::needless_return)]
+
+use glam::Vec2;
+use quantum::globals::*;
+use quantum::molecule::MoleculeTemplate;
+use quantum::world::World;
+use rapier2d::prelude::{ImpulseJointHandle, RevoluteJointBuilder, RigidBodyHandle};
+
+
+const SPACING: f32 = 14.0;
+
+/// Empty world where touching quants don't bond, so only the test changes the bond graph.
+fn make_world() -> World {
+    let settings = Settings { seed: 8, quant_min_num: 0, bond_activation: 0.0, ..Default::default() };
+    let mut world = World::new(settings);
+    world.verbose = false;
+    return world;
+}
+
+fn spawn_chain(world: &mut World, elements: &[&str], pos: Vec2) -> Vec<RigidBodyHandle> {
+    let chain = MoleculeTemplate::chain("chain", elements, SPACING);
+    return world.spawn_molecule(&chain, pos, 0.0).unwrap();
+}
+
+fn bond_between(world: &World, a: RigidBodyHandle, b: RigidBodyHandle) -> ImpulseJointHandle {
+    let bounds = &world.get_quant(b).unwrap().bounds;
+    return *world.get_quant(a).unwrap().bounds.iter().find(|h| bounds.contains(h)).unwrap();
+}
+
+fn molecule_id(world: &World, rbh: RigidBodyHandle) -> u64 {
+    return world.tracker.get_molecule_of(rbh).unwrap().id;
+}
+
+#[test]
+fn larger_fragment_keeps_id_on_split() {
+    let mut world = make_world();
+    let chain = spawn_chain(&mut world, &["G", "B", "G", "B", "G"], Vec2::new(300.0, 300.0));
+    world.update_molecules();
+    let id = molecule_id(&world, chain[0]);
+    assert_eq!(world.tracker.get(id).unwrap().size(), 5);
+
+    let bond = bond_between(&world, chain[1], chain[2]);
+    world.remove_bond(bond);
+    world.update_molecules();
+    assert_eq!(world.tracker.count(), 2);
+    assert_eq!(molecule_id(&world, chain[2]), id);
+    assert_eq!(world.tracker.get(id).unwrap().size(), 3);
+    let small = molecule_id(&world, chain[0]);
+    assert_ne!(small, id);
+    assert_eq!(molecule_id(&world, chain[1]), small);
+}
+
+#[test]
+fn merged_molecule_keeps_id_of_larger_part() {
+    let mut world = make_world();
+    let big = spawn_chain(&mut world, &["G", "B", "G"], Vec2::new(200.0, 300.0));
+    let small = spawn_chain(&mut world, &["G", "G"], Vec2::new(200.0 + 3.0 * SPACING, 300.0));
+    world.update_molecules();
+    let (big_id, small_id) = (molecule_id(&world, big[0]), molecule_id(&world, small[0]));
+    assert_ne!(big_id, small_id);
+    let first_seen = world.tracker.get(big_id).unwrap().first_seen;
+
+    world.step();
+    world.add_bond(big[2], small[0], RevoluteJointBuilder::new());
+    world.update_molecules();
+    assert_eq!(world.tracker.count(), 1);
+    let merged = world.tracker.get_molecule_of(small[1]).unwrap();
+    assert_eq!((merged.id, merged.size()), (big_id, 5));
+    assert_eq!(merged.first_seen, first_seen);
+    assert!(world.tracker.get(small_id).is_none());
+}
+
+#[test]
+fn unchanged_molecule_keeps_id() {
+    let mut world = make_world();
+    let chain = spawn_chain(&mut world, &["G", "B", "G"], Vec2::new(300.0, 300.0));
+    let lone = world.add_element(0).unwrap();
+    world.update_molecules();
+    let (id, lone_id) = (molecule_id(&world, chain[1]), molecule_id(&world, lone));
+    let fingerprint = world.tracker.get(id).unwrap().fingerprint;
+    for _ in 0..300 {
+        world.step();
+        assert_eq!(molecule_id(&world, chain[0]), id);
+        assert_eq!(molecule_id(&world, lone), lone_id);
+        assert_eq!(world.tracker.get(id).unwrap().fingerprint, fingerprint);
+    }
+    assert_eq!(world.tracker.count(), 2);
+}